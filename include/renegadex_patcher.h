@@ -0,0 +1,214 @@
+#ifndef RENEGADEX_PATCHER_H
+#define RENEGADEX_PATCHER_H
+
+/* Generated by cbindgen from src/c_api, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The result of every C API call
+ */
+typedef enum RenxErrorCode {
+  RENX_ERROR_CODE_OK = 0,
+  /**
+   * A handle or required argument was NULL
+   */
+  RENX_ERROR_CODE_NULL_POINTER = 1,
+  /**
+   * An argument holds an unusable value, such as a string that is not valid UTF-8
+   */
+  RENX_ERROR_CODE_INVALID_ARGUMENT = 2,
+  /**
+   * The call does not fit the state of the patcher, e.g. starting it twice or resuming when it is not paused
+   */
+  RENX_ERROR_CODE_INVALID_STATE = 3,
+  RENX_ERROR_CODE_IO = 4,
+  RENX_ERROR_CODE_DOWNLOAD = 5,
+  RENX_ERROR_CODE_NO_MIRRORS = 6,
+  RENX_ERROR_CODE_HASH_MISMATCH = 7,
+  RENX_ERROR_CODE_INVALID_INSTRUCTIONS = 8,
+  RENX_ERROR_CODE_INSUFFICIENT_DISK_SPACE = 9,
+  RENX_ERROR_CODE_CANCELLED = 10,
+  /**
+   * The library panicked, this is a bug
+   */
+  RENX_ERROR_CODE_PANIC = 11,
+  RENX_ERROR_CODE_OTHER = 12,
+} RenxErrorCode;
+
+/**
+ * A `Patcher` together with the tokio runtime it runs on
+ */
+typedef struct RenxPatcher RenxPatcher;
+
+/**
+ * Collects the settings of a `RenxPatcher`, create one with `renx_builder_new`
+ */
+typedef struct RenxPatcherBuilder RenxPatcherBuilder;
+
+/**
+ * A snapshot of `Progress` handed to the progress callback
+ */
+typedef struct RenxProgress {
+  /**
+   * What the patcher is currently doing, only valid for the duration of the callback
+   */
+  const char *current_action;
+  uint64_t processed_instructions;
+  uint64_t total_instructions;
+  uint64_t downloaded_files;
+  uint64_t total_download_files;
+  uint64_t downloaded_bytes;
+  uint64_t total_download_bytes;
+  uint64_t patched_files;
+  uint64_t ready_to_patch_files;
+  uint64_t total_patch_files;
+} RenxProgress;
+
+/**
+ * Called about four times per second while patching, from one of the patcher's threads
+ */
+typedef void (*RenxProgressCallback)(void *user_data, const struct RenxProgress *progress);
+
+/**
+ * Called every time a run finished, from one of the patcher's threads
+ */
+typedef void (*RenxCompletionCallback)(void *user_data, enum RenxErrorCode code, const char *message);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns the message of the last failed call on this thread, or NULL if none failed yet
+ *
+ * The string is owned by the library and stays valid until the next failing call on the same thread.
+ */
+const char *renx_last_error(void);
+
+/**
+ * Creates a builder with the default settings, free it with `renx_builder_free` unless it is passed to `renx_builder_build`
+ */
+struct RenxPatcherBuilder *renx_builder_new(void);
+
+/**
+ * Frees a builder that was not built, passing NULL is allowed
+ */
+void renx_builder_free(struct RenxPatcherBuilder *builder);
+
+enum RenxErrorCode renx_builder_set_software_location(struct RenxPatcherBuilder *builder,
+                                                      const char *software_location);
+
+/**
+ * Adds a mirror to download from, `url` should end with a slash
+ */
+enum RenxErrorCode renx_builder_add_mirror(struct RenxPatcherBuilder *builder,
+                                           const char *name,
+                                           const char *url);
+
+enum RenxErrorCode renx_builder_set_version(struct RenxPatcherBuilder *builder,
+                                            const char *version);
+
+enum RenxErrorCode renx_builder_set_instructions_hash(struct RenxPatcherBuilder *builder,
+                                                      const char *instructions_hash);
+
+/**
+ * Skips instructions that fail instead of aborting the patch
+ */
+enum RenxErrorCode renx_builder_set_lenient_validation(struct RenxPatcherBuilder *builder,
+                                                       bool lenient);
+
+/**
+ * Moves unversioned files into quarantine during a factory reset instead of deleting them, quarantines older than `max_age_seconds` are purged, 0 keeps them forever
+ */
+enum RenxErrorCode renx_builder_set_quarantine(struct RenxPatcherBuilder *builder,
+                                               bool quarantine,
+                                               uint64_t max_age_seconds);
+
+enum RenxErrorCode renx_builder_set_case_insensitive_paths(struct RenxPatcherBuilder *builder,
+                                                           bool case_insensitive_paths);
+
+/**
+ * Places content shared by several files with hard links instead of copies
+ */
+enum RenxErrorCode renx_builder_set_hard_link_duplicates(struct RenxPatcherBuilder *builder,
+                                                         bool hard_link);
+
+/**
+ * Shares files with other installs through the content store at `location`, a `max_size` of 0 means unlimited
+ */
+enum RenxErrorCode renx_builder_set_content_store(struct RenxPatcherBuilder *builder,
+                                                  const char *location,
+                                                  uint64_t max_size);
+
+/**
+ * Retains the downloaded patch files in mirror layout in `location`, NULL retains them inside of the software location
+ */
+enum RenxErrorCode renx_builder_retain_patch_files(struct RenxPatcherBuilder *builder,
+                                                   const char *location);
+
+/**
+ * Limits patch files, together with the files they patch, to `budget` bytes at the same time, 0 disables the low-disk mode. Building fails when patch files are also retained
+ */
+enum RenxErrorCode renx_builder_set_disk_space_budget(struct RenxPatcherBuilder *builder,
+                                                      uint64_t budget);
+
+/**
+ * `user_data` is passed to every call of `callback`, which happens on one of the patcher's threads
+ */
+enum RenxErrorCode renx_builder_set_progress_callback(struct RenxPatcherBuilder *builder,
+                                                      RenxProgressCallback callback,
+                                                      void *user_data);
+
+/**
+ * `callback` is called every time a run finished, with `RENX_ERROR_CODE_OK` and a NULL message on success
+ */
+enum RenxErrorCode renx_builder_set_completion_callback(struct RenxPatcherBuilder *builder,
+                                                        RenxCompletionCallback callback,
+                                                        void *user_data);
+
+/**
+ * Creates the patcher and frees `builder`, even when it fails
+ *
+ * Returns NULL on failure, `renx_last_error` describes why. Free the patcher with `renx_patcher_free`.
+ */
+struct RenxPatcher *renx_builder_build(struct RenxPatcherBuilder *builder);
+
+/**
+ * Starts patching in the background, use `renx_patcher_wait` or the completion callback to find out when it is done
+ */
+enum RenxErrorCode renx_patcher_start(const struct RenxPatcher *patcher);
+
+/**
+ * Starts a factory reset in the background: patches and then removes every unversioned file
+ */
+enum RenxErrorCode renx_patcher_factory_reset(const struct RenxPatcher *patcher);
+
+enum RenxErrorCode renx_patcher_pause(const struct RenxPatcher *patcher);
+
+enum RenxErrorCode renx_patcher_resume(const struct RenxPatcher *patcher);
+
+/**
+ * Blocks until the running patch is done and returns its result, returns `RENX_ERROR_CODE_INVALID_STATE` if nothing was started
+ */
+enum RenxErrorCode renx_patcher_wait(const struct RenxPatcher *patcher);
+
+/**
+ * Stops the running patch and blocks until it stopped, the patcher can be started again afterwards
+ */
+enum RenxErrorCode renx_patcher_cancel(const struct RenxPatcher *patcher);
+
+/**
+ * Cancels anything that is still running and frees the patcher, passing NULL is allowed
+ */
+void renx_patcher_free(struct RenxPatcher *patcher);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RENEGADEX_PATCHER_H */
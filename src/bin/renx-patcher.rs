@@ -0,0 +1,198 @@
+//! Headless patcher for Renegade X installs, e.g. dedicated servers
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+
+use clap::{Args, Parser, Subcommand};
+use renegadex_patcher::{blocking, human_readable_bytesize, Error, NamedUrl, PatchReport, PatcherBuilder, Plan, Progress, ValidationMode};
+use serde::Deserialize;
+
+/// Exit code of `verify` when files differ from the instructions
+const EXIT_FILES_DIFFER: u8 = 3;
+
+#[derive(Parser)]
+#[command(name = "renx-patcher", about = "Updates a Renegade X installation from the command line", disable_version_flag = true)]
+struct Cli {
+  #[command(flatten)]
+  settings: Settings,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Updates the installation to the given version, does nothing if its state file says it is already on it
+  Patch,
+  /// Lists the files that differ from the given version without changing anything, exits with 3 if any do
+  Verify,
+  /// Verifies every file and redownloads the ones that are missing or broken, even if the installation is recorded as up to date
+  Repair,
+  /// Repairs the installation and removes every file that is not part of it
+  FactoryReset,
+  /// Shows what patching would download and how much disk space it needs, without changing anything
+  Plan,
+}
+
+#[derive(Args, Default, Deserialize)]
+#[serde(default, rename_all = "snake_case")]
+struct Settings {
+  /// JSON file with any of the settings below, flags take precedence over it
+  #[arg(long, global = true)]
+  #[serde(skip)]
+  config: Option<PathBuf>,
+  /// The directory of the installation
+  #[arg(long, global = true)]
+  software_location: Option<PathBuf>,
+  /// The version to patch to
+  #[arg(long, global = true)]
+  version: Option<String>,
+  /// The SHA256 hash of the instructions.json of the version
+  #[arg(long, global = true)]
+  instructions_hash: Option<String>,
+  /// A mirror URL to download from, can be repeated
+  #[arg(long = "mirror", global = true)]
+  mirrors: Vec<String>,
+  /// Skip files whose instructions fail instead of aborting
+  #[arg(long, global = true)]
+  lenient: bool,
+  /// Do not print progress
+  #[arg(long, global = true)]
+  #[serde(skip)]
+  quiet: bool,
+  /// Write newline-delimited JSON events to stdout
+  #[arg(long, global = true)]
+  #[serde(skip)]
+  json: bool,
+}
+
+impl Settings {
+  /// Fills in everything that was not passed as a flag from the config file
+  fn merge_config(mut self) -> Result<Self, Error> {
+    let config = match &self.config {
+      Some(config) => config,
+      None => return Ok(self),
+    };
+    let text = std::fs::read_to_string(config)?;
+    let config: Settings = serde_json::from_str(&text)?;
+    self.software_location = self.software_location.or(config.software_location);
+    self.version = self.version.or(config.version);
+    self.instructions_hash = self.instructions_hash.or(config.instructions_hash);
+    if self.mirrors.is_empty() {
+      self.mirrors = config.mirrors;
+    }
+    self.lenient |= config.lenient;
+    Ok(self)
+  }
+
+  fn into_builder(self) -> Result<PatcherBuilder, Error> {
+    let mirrors = self.mirrors.into_iter().enumerate().map(|(index, url)| NamedUrl { name: format!("mirror {}", index + 1), url }).collect();
+    let mut builder = PatcherBuilder::new();
+    builder.set_software_location(self.software_location.ok_or(Error::MissingField("software_location"))?);
+    builder.set_software_information(mirrors, self.version.ok_or(Error::MissingField("version"))?, self.instructions_hash.ok_or(Error::MissingField("instructions_hash"))?);
+    builder.set_validation_mode(if self.lenient { ValidationMode::Lenient } else { ValidationMode::Strict });
+    if !self.quiet {
+      builder.set_progress_callback(Box::new(print_progress));
+    }
+    if self.json {
+      builder.set_event_writer(std::io::stdout());
+    }
+    Ok(builder)
+  }
+}
+
+fn print_progress(progress: &Progress) {
+  let bytes = |value: &std::sync::atomic::AtomicU64| human_readable_bytesize(value.load(Ordering::Relaxed) as i64);
+  let count = |value: &std::sync::atomic::AtomicU64| value.load(Ordering::Relaxed);
+  let mut stderr = std::io::stderr().lock();
+  let _ = write!(stderr, "\r\x1b[2K{} | verified {}/{} | downloaded {}/{} | patched {}/{}",
+    progress.get_current_action().unwrap_or_default(),
+    count(&progress.processed_instructions.0), count(&progress.processed_instructions.1),
+    bytes(&progress.downloaded_bytes.0), bytes(&progress.downloaded_bytes.1),
+    count(&progress.patched_files.0), count(&progress.patched_files.2));
+  let _ = stderr.flush();
+}
+
+fn print_plan(out: &mut dyn Write, plan: &Plan) -> Result<(), Error> {
+  for update in &plan.updates {
+    writeln!(out, "{} {} ({})", if update.delta { "delta " } else { "full  " }, update.path.display(), human_readable_bytesize(update.download_size as i64))?;
+  }
+  for deletion in &plan.deletions {
+    writeln!(out, "delete {}", deletion.display())?;
+  }
+  writeln!(out, "{} file(s) to patch, {} file(s) to delete", plan.updates.len(), plan.deletions.len())?;
+  Ok(())
+}
+
+fn print_report(report: &PatchReport) {
+  eprintln!("\nVerified {} file(s), downloaded {} delta and {} full patch file(s), patched {}, deleted {}, restored {} from backup, skipped {}",
+    report.verified_files, report.delta_downloads, report.full_downloads, report.patched_files, report.deleted_files, report.restored_files, report.skipped_files);
+  for (mirror, bytes) in &report.mirror_bytes {
+    eprintln!("{} from {}", human_readable_bytesize(*bytes as i64), mirror);
+  }
+  for (phase, duration) in &report.phases {
+    eprintln!("{:.1}s {}", duration.as_secs_f64(), phase);
+  }
+  for warning in &report.warnings {
+    eprintln!("warning: {}", warning);
+  }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, Error> {
+  let quiet = cli.settings.quiet;
+  // stdout is reserved for the JSON events with --json, so everything meant for people goes to stderr
+  let mut out: Box<dyn Write> = if cli.settings.json { Box::new(std::io::stderr()) } else { Box::new(std::io::stdout()) };
+  let patcher = blocking::Patcher::new(cli.settings.merge_config()?.into_builder()?)?;
+  match cli.command {
+    Command::Patch | Command::Repair | Command::FactoryReset => {
+      if let Command::Patch = cli.command {
+        if !patcher.needs_patching()? {
+          if !quiet {
+            eprintln!("Already up to date, use repair to verify every file");
+          }
+          return Ok(ExitCode::SUCCESS);
+        }
+      }
+      if let Command::FactoryReset = cli.command {
+        patcher.factory_reset()?;
+      } else {
+        patcher.start()?;
+      }
+      let report = patcher.wait()?;
+      if !quiet {
+        print_report(&report);
+      }
+    },
+    Command::Verify => {
+      let plan = patcher.plan()?;
+      print_plan(&mut out, &plan)?;
+      if !plan.updates.is_empty() || !plan.deletions.is_empty() {
+        return Ok(ExitCode::from(EXIT_FILES_DIFFER));
+      }
+    },
+    Command::Plan => {
+      let plan = patcher.plan()?;
+      print_plan(&mut out, &plan)?;
+      let disk_space = plan.disk_space;
+      writeln!(out, "Requires {} to download and {} while patching, {} available", human_readable_bytesize(disk_space.downloads as i64), human_readable_bytesize(disk_space.patching as i64), human_readable_bytesize(disk_space.available as i64))?;
+      if !disk_space.is_sufficient() {
+        writeln!(out, "Not enough disk space")?;
+      }
+    },
+  }
+  if !quiet {
+    eprintln!();
+  }
+  Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+  match run(Cli::parse()) {
+    Ok(exit_code) => exit_code,
+    Err(e) => {
+      eprintln!("\nerror: {}", e);
+      ExitCode::FAILURE
+    }
+  }
+}
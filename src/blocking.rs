@@ -0,0 +1,159 @@
+//! A synchronous facade over `Patcher` for applications without an async runtime
+
+use std::sync::{Arc, Mutex};
+
+use tokio::runtime::{Handle, Runtime};
+
+use crate::pausable::BackgroundService;
+use crate::{DiskSpace, Error, PatchHandle, PatchReport, PatcherBuilder, Plan};
+
+type CompletionHook = Box<dyn Fn(Option<&Error>) + Send + Sync>;
+
+/// A `Patcher` that owns the tokio runtime it runs on, every method blocks until it is done
+///
+/// The methods take `&self`, so `pause`, `resume` and `cancel` can be called from another thread while `wait` blocks.
+/// It must not be used or dropped from within a tokio runtime, as blocking on or dropping its own runtime there panics,
+/// so it can not be created inside of one. Async applications use `crate::Patcher` instead.
+pub struct Patcher {
+  runtime: Runtime,
+  patcher: Mutex<crate::Patcher>,
+  handle: Mutex<Option<PatchHandle>>,
+}
+
+impl Patcher {
+  /// Builds the patcher, `wait` returns the outcome of each run
+  ///
+  /// Fails with `InvalidState` when called from within a tokio runtime.
+  pub fn new(builder: PatcherBuilder) -> Result<Self, Error> {
+    Self::with_completion(builder, Box::new(|_| {}))
+  }
+
+  /// Builds the patcher, `completion` is called with the error, if any, every time a run finished
+  pub(crate) fn with_completion(builder: PatcherBuilder, completion: CompletionHook) -> Result<Self, Error> {
+    if Handle::try_current().is_ok() {
+      return Err(Error::InvalidState("The blocking patcher can not be used from within a tokio runtime"));
+    }
+    let patcher = builder.build()?;
+    let completion = Arc::new(completion);
+    let success_completion = completion.clone();
+    patcher.add_success_callback(move |_| success_completion(None))?;
+    patcher.add_failure_callback(move |error| completion(Some(error)))?;
+    Ok(Self {
+      runtime: tokio::runtime::Builder::new_multi_thread().enable_all().build()?,
+      patcher: Mutex::new(patcher),
+      handle: Mutex::new(None),
+    })
+  }
+
+  /// Starts patching in the background, use `wait` to block until it is done
+  pub fn start(&self) -> Result<(), Error> {
+    let mut patcher = self.patcher.lock()?;
+    *self.handle.lock()? = Some(self.runtime.block_on(patcher.start_patching())?);
+    Ok(())
+  }
+
+  /// Starts a factory reset in the background: patches and then removes every unversioned file
+  pub fn factory_reset(&self) -> Result<(), Error> {
+    let mut patcher = self.patcher.lock()?;
+    *self.handle.lock()? = Some(self.runtime.block_on(patcher.factory_reset())?);
+    Ok(())
+  }
+
+  /// Whether the install is not known to be up to date according to its state file, see `Patcher::needs_patching`
+  pub fn needs_patching(&self) -> Result<bool, Error> {
+    self.patcher.lock()?.needs_patching()
+  }
+
+  /// Blocks until the running patch is done and returns its report
+  pub fn wait(&self) -> Result<PatchReport, Error> {
+    let handle = self.handle.lock()?.take().ok_or(Error::InvalidState("The patcher is not running"))?;
+    self.runtime.block_on(handle)
+  }
+
+  /// Determines what patching would change, without changing any files, see `Patcher::plan`
+  pub fn plan(&self) -> Result<Plan, Error> {
+    let patcher = self.patcher.lock()?;
+    self.runtime.block_on(patcher.plan())
+  }
+
+  /// Estimates the disk space patching needs, see `Patcher::disk_space_requirement`
+  pub fn disk_space_requirement(&self) -> Result<DiskSpace, Error> {
+    let patcher = self.patcher.lock()?;
+    self.runtime.block_on(patcher.disk_space_requirement())
+  }
+
+  pub fn pause(&self) -> Result<(), Error> {
+    self.patcher.lock()?.pause().map_err(|_| Error::InvalidState("The patcher is already paused"))
+  }
+
+  pub fn resume(&self) -> Result<(), Error> {
+    self.patcher.lock()?.resume().map_err(|_| Error::InvalidState("The patcher is not paused"))
+  }
+
+  /// Stops the running patch and blocks until it stopped, the patcher can be started again afterwards
+  pub fn cancel(&self) -> Result<(), Error> {
+    self.patcher.lock()?.context.stop().map_err(|_| Error::InvalidState("The patcher was already cancelled"))?;
+    if let Some(handle) = self.handle.lock()?.take() {
+      // The run ends with FutureCancelled, which is the expected outcome of cancelling
+      let _ = self.runtime.block_on(handle);
+    }
+    Ok(())
+  }
+}
+
+impl Drop for Patcher {
+  fn drop(&mut self) {
+    if let Ok(patcher) = self.patcher.lock() {
+      let _ = patcher.context.stop();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NamedUrl;
+    use crate::structures::TempDir;
+
+    #[test]
+    fn state_errors_without_a_running_patch() {
+        let temp_dir = TempDir::new("blocking");
+        let mut builder = PatcherBuilder::new();
+        builder.set_software_location(temp_dir.path());
+        builder.set_software_information(vec![NamedUrl { name: "local".to_string(), url: "http://127.0.0.1:1/".to_string() }], "1.0".to_string(), "0".repeat(64));
+        let patcher = Patcher::new(builder).unwrap();
+        assert!(matches!(patcher.wait(), Err(Error::InvalidState(_))));
+        assert!(matches!(patcher.resume(), Err(Error::InvalidState(_))));
+        patcher.pause().unwrap();
+        assert!(matches!(patcher.pause(), Err(Error::InvalidState(_))));
+        patcher.resume().unwrap();
+    }
+
+    #[test]
+    fn starts_again_after_a_failed_run() {
+        let temp_dir = TempDir::new("blocking_rerun");
+        let mut builder = PatcherBuilder::new();
+        builder.set_software_location(temp_dir.path());
+        builder.set_software_information(vec![NamedUrl { name: "local".to_string(), url: "http://127.0.0.1:1/".to_string() }], "1.0".to_string(), "0".repeat(64));
+        let failures = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = failures.clone();
+        let patcher = Patcher::with_completion(builder, Box::new(move |error| {
+            assert!(error.is_some());
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        })).unwrap();
+        for _ in 0..2 {
+            patcher.start().unwrap();
+            assert!(patcher.wait().is_err());
+        }
+        assert_eq!(failures.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn refuses_to_run_inside_of_a_runtime() {
+        let temp_dir = TempDir::new("blocking_in_runtime");
+        let mut builder = PatcherBuilder::new();
+        builder.set_software_location(temp_dir.path());
+        builder.set_software_information(vec![NamedUrl { name: "local".to_string(), url: "http://127.0.0.1:1/".to_string() }], "1.0".to_string(), "0".repeat(64));
+        assert!(matches!(Patcher::new(builder), Err(Error::InvalidState(_))));
+    }
+}
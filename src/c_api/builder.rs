@@ -0,0 +1,208 @@
+use std::ffi::{c_char, c_void};
+use std::time::Duration;
+
+use crate::c_api::{guard, to_str, RenxErrorCode, RenxProgress, RenxProgressCallback, UserData};
+use crate::c_api::patcher::{RenxCompletionCallback, RenxPatcher};
+use crate::{DeduplicationMode, Error, NamedUrl, PatchFileRetention, PatcherBuilder, UnversionedMode, ValidationMode};
+
+/// Collects the settings of a `RenxPatcher`, create one with `renx_builder_new`
+pub struct RenxPatcherBuilder {
+  pub(crate) builder: PatcherBuilder,
+  pub(crate) mirrors: Vec<NamedUrl>,
+  pub(crate) version: Option<String>,
+  pub(crate) instructions_hash: Option<String>,
+  pub(crate) completion_callback: Option<(unsafe extern "C" fn(*mut c_void, RenxErrorCode, *const c_char), UserData)>,
+}
+
+/// Runs `function` on the builder behind `builder`, checking it for NULL first
+unsafe fn with_builder(builder: *mut RenxPatcherBuilder, function: impl FnOnce(&mut RenxPatcherBuilder) -> Result<(), Error>) -> RenxErrorCode {
+  guard(|| {
+    let builder = builder.as_mut().ok_or(Error::MissingField("builder"))?;
+    function(builder)
+  })
+}
+
+/// Creates a builder with the default settings, free it with `renx_builder_free` unless it is passed to `renx_builder_build`
+#[no_mangle]
+pub extern "C" fn renx_builder_new() -> *mut RenxPatcherBuilder {
+  Box::into_raw(Box::new(RenxPatcherBuilder {
+    builder: PatcherBuilder::new(),
+    mirrors: Vec::new(),
+    version: None,
+    instructions_hash: None,
+    completion_callback: None,
+  }))
+}
+
+/// Frees a builder that was not built, passing NULL is allowed
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_free(builder: *mut RenxPatcherBuilder) {
+  if !builder.is_null() {
+    drop(Box::from_raw(builder));
+  }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_software_location(builder: *mut RenxPatcherBuilder, software_location: *const c_char) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.builder.set_software_location(to_str(software_location, "software_location")?);
+    Ok(())
+  })
+}
+
+/// Adds a mirror to download from, `url` should end with a slash
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_add_mirror(builder: *mut RenxPatcherBuilder, name: *const c_char, url: *const c_char) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.mirrors.push(NamedUrl {
+      name: to_str(name, "name")?.to_string(),
+      url: to_str(url, "url")?.to_string(),
+    });
+    Ok(())
+  })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_version(builder: *mut RenxPatcherBuilder, version: *const c_char) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.version = Some(to_str(version, "version")?.to_string());
+    Ok(())
+  })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_instructions_hash(builder: *mut RenxPatcherBuilder, instructions_hash: *const c_char) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.instructions_hash = Some(to_str(instructions_hash, "instructions_hash")?.to_string());
+    Ok(())
+  })
+}
+
+/// Skips instructions that fail instead of aborting the patch
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_lenient_validation(builder: *mut RenxPatcherBuilder, lenient: bool) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.builder.set_validation_mode(if lenient { ValidationMode::Lenient } else { ValidationMode::Strict });
+    Ok(())
+  })
+}
+
+/// Moves unversioned files into quarantine during a factory reset instead of deleting them, quarantines older than `max_age_seconds` are purged, 0 keeps them forever
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_quarantine(builder: *mut RenxPatcherBuilder, quarantine: bool, max_age_seconds: u64) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.builder.set_unversioned_mode(if quarantine { UnversionedMode::Quarantine } else { UnversionedMode::Delete });
+    builder.builder.set_quarantine_max_age(if max_age_seconds == 0 { None } else { Some(Duration::from_secs(max_age_seconds)) });
+    Ok(())
+  })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_case_insensitive_paths(builder: *mut RenxPatcherBuilder, case_insensitive_paths: bool) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.builder.set_case_insensitive_paths(case_insensitive_paths);
+    Ok(())
+  })
+}
+
+/// Places content shared by several files with hard links instead of copies
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_hard_link_duplicates(builder: *mut RenxPatcherBuilder, hard_link: bool) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.builder.set_deduplication_mode(if hard_link { DeduplicationMode::HardLink } else { DeduplicationMode::Copy });
+    Ok(())
+  })
+}
+
+/// Shares files with other installs through the content store at `location`, a `max_size` of 0 means unlimited
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_content_store(builder: *mut RenxPatcherBuilder, location: *const c_char, max_size: u64) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.builder.set_content_store(to_str(location, "location")?, if max_size == 0 { None } else { Some(max_size) });
+    Ok(())
+  })
+}
+
+/// Retains the downloaded patch files in mirror layout in `location`, NULL retains them inside of the software location
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_retain_patch_files(builder: *mut RenxPatcherBuilder, location: *const c_char) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    let retention = if location.is_null() {
+      PatchFileRetention::Retain
+    } else {
+      PatchFileRetention::RetainIn(to_str(location, "location")?.into())
+    };
+    builder.builder.set_patch_file_retention(retention);
+    Ok(())
+  })
+}
+
+/// Limits patch files, together with the files they patch, to `budget` bytes at the same time, 0 disables the low-disk mode. Building fails when patch files are also retained
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_disk_space_budget(builder: *mut RenxPatcherBuilder, budget: u64) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.builder.set_disk_space_budget(if budget == 0 { None } else { Some(budget) });
+    Ok(())
+  })
+}
+
+/// `user_data` is passed to every call of `callback`, which happens on one of the patcher's threads
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_progress_callback(builder: *mut RenxPatcherBuilder, callback: RenxProgressCallback, user_data: *mut c_void) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    let callback = callback.ok_or(Error::MissingField("callback"))?;
+    let user_data = UserData(user_data);
+    builder.builder.set_progress_callback(Box::new(move |progress| {
+      let user_data = user_data;
+      RenxProgress::report(progress, callback, user_data.0)
+    }));
+    Ok(())
+  })
+}
+
+/// `callback` is called every time a run finished, with `RENX_ERROR_CODE_OK` and a NULL message on success
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_set_completion_callback(builder: *mut RenxPatcherBuilder, callback: RenxCompletionCallback, user_data: *mut c_void) -> RenxErrorCode {
+  with_builder(builder, |builder| {
+    builder.completion_callback = Some((callback.ok_or(Error::MissingField("callback"))?, UserData(user_data)));
+    Ok(())
+  })
+}
+
+/// Creates the patcher and frees `builder`, even when it fails
+///
+/// Returns NULL on failure, `renx_last_error` describes why. Free the patcher with `renx_patcher_free`.
+#[no_mangle]
+pub unsafe extern "C" fn renx_builder_build(builder: *mut RenxPatcherBuilder) -> *mut RenxPatcher {
+  let mut patcher = std::ptr::null_mut();
+  let code = guard(|| {
+    if builder.is_null() {
+      return Err(Error::MissingField("builder"));
+    }
+    let builder = *Box::from_raw(builder);
+    patcher = Box::into_raw(Box::new(RenxPatcher::new(builder)?));
+    Ok(())
+  });
+  if code != RenxErrorCode::Ok {
+    return std::ptr::null_mut();
+  }
+  patcher
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::{CStr, CString};
+
+    #[test]
+    fn build_reports_missing_fields_through_last_error() {
+        let location = CString::new("/tmp/renx").unwrap();
+        unsafe {
+            let builder = renx_builder_new();
+            assert_eq!(renx_builder_set_software_location(builder, location.as_ptr()), RenxErrorCode::Ok);
+            assert_eq!(renx_builder_set_version(builder, std::ptr::null()), RenxErrorCode::NullPointer);
+            assert!(renx_builder_build(builder).is_null());
+            assert!(CStr::from_ptr(crate::c_api::renx_last_error()).to_str().unwrap().contains("version"));
+        }
+    }
+}
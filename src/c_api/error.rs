@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+
+use crate::Error;
+
+/// The result of every C API call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenxErrorCode {
+  Ok = 0,
+  /// A handle or required argument was NULL
+  NullPointer = 1,
+  /// An argument holds an unusable value, such as a string that is not valid UTF-8
+  InvalidArgument = 2,
+  /// The call does not fit the state of the patcher, e.g. starting it twice or resuming when it is not paused
+  InvalidState = 3,
+  Io = 4,
+  Download = 5,
+  NoMirrors = 6,
+  HashMismatch = 7,
+  InvalidInstructions = 8,
+  InsufficientDiskSpace = 9,
+  Cancelled = 10,
+  /// The library panicked, this is a bug
+  Panic = 11,
+  Other = 12,
+}
+
+impl From<&Error> for RenxErrorCode {
+  fn from(error: &Error) -> Self {
+    match error {
+      Error::MissingField(_) => Self::NullPointer,
+      Error::InvalidInput(_) | Error::InvalidPattern(_) => Self::InvalidArgument,
+      Error::AlreadyStarted() | Error::InvalidState(_) => Self::InvalidState,
+      Error::IoError(_) | Error::FileLocked() | Error::StripPrefix(_) => Self::Io,
+      Error::InvalidUri(_) | Error::HttpError(_) | Error::InvalidStatus(_) | Error::DownloadTimeout(_) | Error::DownloadError(_) | Error::DownloadAsyncError(_) | Error::InvalidServer() | Error::OutOfRetries(_) => Self::Download,
+      Error::NoMirrors() => Self::NoMirrors,
+      Error::HashMismatch(_, _, _) => Self::HashMismatch,
+      Error::FailedInstructions(_) | Error::InvalidManifest(_, _) | Error::UnsupportedManifestVersion(_) | Error::UnsafePath(_, _) | Error::InvalidJson(_, _) | Error::JsonError(_) | Error::NotUtf8(_) => Self::InvalidInstructions,
+      Error::InsufficientDiskSpace { .. } => Self::InsufficientDiskSpace,
+      Error::FutureCancelled() => Self::Cancelled,
+      _ => Self::Other,
+    }
+  }
+}
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_message(message: String) {
+  // Interior NUL characters would truncate the message, replace them rather than losing it
+  let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+  LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+pub(crate) fn set_last_error(error: &Error) -> RenxErrorCode {
+  set_message(error.to_string());
+  error.into()
+}
+
+pub(crate) fn set_last_panic() -> RenxErrorCode {
+  set_message(format!("renegadex_patcher panicked"));
+  RenxErrorCode::Panic
+}
+
+/// Returns the message of the last failed call on this thread, or NULL if none failed yet
+///
+/// The string is owned by the library and stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn renx_last_error() -> *const c_char {
+  LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map(|message| message.as_ptr()).unwrap_or(std::ptr::null()))
+}
@@ -0,0 +1,49 @@
+//! A C ABI over `PatcherBuilder` and `Patcher`, see `include/renegadex_patcher.h`
+//!
+//! Every function returns a `RenxErrorCode`, or NULL for functions returning a handle, and leaves a message for `renx_last_error` on failure.
+//! A `RenxPatcher` owns its own tokio runtime, so callers do not need any async machinery.
+//!
+//! Safety: every pointer passed in must be NULL or valid, strings must be NUL-terminated, and handles must not be used after they were freed.
+#![allow(clippy::missing_safety_doc)]
+
+mod error;
+pub use error::{RenxErrorCode, renx_last_error};
+
+mod progress;
+pub use progress::{RenxProgress, RenxProgressCallback};
+
+mod builder;
+pub use builder::*;
+
+mod patcher;
+pub use patcher::*;
+
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::Error;
+
+/// Runs `function`, converting errors and panics into an error code so they never unwind into C
+pub(crate) fn guard(function: impl FnOnce() -> Result<(), Error>) -> RenxErrorCode {
+  match catch_unwind(AssertUnwindSafe(function)) {
+    Ok(Ok(())) => RenxErrorCode::Ok,
+    Ok(Err(e)) => error::set_last_error(&e),
+    Err(_) => error::set_last_panic(),
+  }
+}
+
+/// Borrows a NUL-terminated UTF-8 string from C
+pub(crate) unsafe fn to_str<'a>(string: *const c_char, name: &'static str) -> Result<&'a str, Error> {
+  if string.is_null() {
+    return Err(Error::MissingField(name));
+  }
+  CStr::from_ptr(string).to_str().map_err(|_| Error::InvalidInput(format!("{} is not valid UTF-8", name)))
+}
+
+/// A pointer passed through from C, it is only ever handed back to the callbacks it was registered with
+#[derive(Clone, Copy)]
+pub(crate) struct UserData(pub(crate) *mut std::ffi::c_void);
+
+// The C caller is responsible for `user_data` being usable from the patcher's threads
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
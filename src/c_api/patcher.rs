@@ -0,0 +1,89 @@
+use std::ffi::{c_char, c_void, CString};
+
+use crate::c_api::{guard, RenxErrorCode};
+use crate::c_api::builder::RenxPatcherBuilder;
+use crate::{blocking, Error};
+
+/// Called every time a run finished, from one of the patcher's threads
+pub type RenxCompletionCallback = Option<unsafe extern "C" fn(user_data: *mut c_void, code: RenxErrorCode, message: *const c_char)>;
+
+/// A `Patcher` together with the tokio runtime it runs on
+pub struct RenxPatcher {
+  pub(crate) patcher: blocking::Patcher,
+}
+
+impl RenxPatcher {
+  pub(crate) fn new(builder: RenxPatcherBuilder) -> Result<Self, Error> {
+    let RenxPatcherBuilder { mut builder, mirrors, version, instructions_hash, completion_callback } = builder;
+    let version = version.ok_or(Error::MissingField("version"))?;
+    let instructions_hash = instructions_hash.ok_or(Error::MissingField("instructions_hash"))?;
+    builder.set_software_information(mirrors, version, instructions_hash);
+
+    let patcher = blocking::Patcher::with_completion(builder, Box::new(move |error| {
+      if let Some((callback, user_data)) = completion_callback {
+        match error {
+          Some(error) => {
+            let message = CString::new(error.to_string().replace('\0', " ")).unwrap_or_default();
+            unsafe { callback(user_data.0, error.into(), message.as_ptr()) };
+          },
+          None => unsafe { callback(user_data.0, RenxErrorCode::Ok, std::ptr::null()) },
+        }
+      }
+    }))?;
+    Ok(Self { patcher })
+  }
+}
+
+/// Runs `function` on the patcher behind `patcher`, checking it for NULL first
+unsafe fn with_patcher(patcher: *const RenxPatcher, function: impl FnOnce(&blocking::Patcher) -> Result<(), Error>) -> RenxErrorCode {
+  guard(|| {
+    let patcher = patcher.as_ref().ok_or(Error::MissingField("patcher"))?;
+    function(&patcher.patcher)
+  })
+}
+
+/// Starts patching in the background, use `renx_patcher_wait` or the completion callback to find out when it is done
+#[no_mangle]
+pub unsafe extern "C" fn renx_patcher_start(patcher: *const RenxPatcher) -> RenxErrorCode {
+  with_patcher(patcher, |patcher| patcher.start())
+}
+
+/// Starts a factory reset in the background: patches and then removes every unversioned file
+#[no_mangle]
+pub unsafe extern "C" fn renx_patcher_factory_reset(patcher: *const RenxPatcher) -> RenxErrorCode {
+  with_patcher(patcher, |patcher| patcher.factory_reset())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renx_patcher_pause(patcher: *const RenxPatcher) -> RenxErrorCode {
+  with_patcher(patcher, |patcher| patcher.pause())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renx_patcher_resume(patcher: *const RenxPatcher) -> RenxErrorCode {
+  with_patcher(patcher, |patcher| patcher.resume())
+}
+
+/// Blocks until the running patch is done and returns its result, returns `RENX_ERROR_CODE_INVALID_STATE` if nothing was started
+#[no_mangle]
+pub unsafe extern "C" fn renx_patcher_wait(patcher: *const RenxPatcher) -> RenxErrorCode {
+  with_patcher(patcher, |patcher| patcher.wait().map(|_| ()))
+}
+
+/// Stops the running patch and blocks until it stopped, the patcher can be started again afterwards
+#[no_mangle]
+pub unsafe extern "C" fn renx_patcher_cancel(patcher: *const RenxPatcher) -> RenxErrorCode {
+  with_patcher(patcher, |patcher| patcher.cancel())
+}
+
+/// Cancels anything that is still running and frees the patcher, passing NULL is allowed
+#[no_mangle]
+pub unsafe extern "C" fn renx_patcher_free(patcher: *mut RenxPatcher) {
+  if patcher.is_null() {
+    return;
+  }
+  let _ = guard(|| {
+    drop(Box::from_raw(patcher));
+    Ok(())
+  });
+}
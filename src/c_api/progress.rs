@@ -0,0 +1,44 @@
+use std::ffi::{c_char, c_void, CString};
+use std::sync::atomic::Ordering;
+
+use crate::Progress;
+
+/// A snapshot of `Progress` handed to the progress callback
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RenxProgress {
+  /// What the patcher is currently doing, only valid for the duration of the callback
+  pub current_action: *const c_char,
+  pub processed_instructions: u64,
+  pub total_instructions: u64,
+  pub downloaded_files: u64,
+  pub total_download_files: u64,
+  pub downloaded_bytes: u64,
+  pub total_download_bytes: u64,
+  pub patched_files: u64,
+  pub ready_to_patch_files: u64,
+  pub total_patch_files: u64,
+}
+
+/// Called about four times per second while patching, from one of the patcher's threads
+pub type RenxProgressCallback = Option<unsafe extern "C" fn(user_data: *mut c_void, progress: *const RenxProgress)>;
+
+impl RenxProgress {
+  /// Takes a snapshot of `progress` and calls `callback` with it
+  pub(crate) fn report(progress: &Progress, callback: unsafe extern "C" fn(*mut c_void, *const RenxProgress), user_data: *mut c_void) {
+    let current_action = CString::new(progress.get_current_action().unwrap_or_default().replace('\0', " ")).unwrap_or_default();
+    let snapshot = Self {
+      current_action: current_action.as_ptr(),
+      processed_instructions: progress.processed_instructions.0.load(Ordering::Relaxed),
+      total_instructions: progress.processed_instructions.1.load(Ordering::Relaxed),
+      downloaded_files: progress.downloaded_files.0.load(Ordering::Relaxed),
+      total_download_files: progress.downloaded_files.1.load(Ordering::Relaxed),
+      downloaded_bytes: progress.downloaded_bytes.0.load(Ordering::Relaxed),
+      total_download_bytes: progress.downloaded_bytes.1.load(Ordering::Relaxed),
+      patched_files: progress.patched_files.0.load(Ordering::Relaxed),
+      ready_to_patch_files: progress.patched_files.1.load(Ordering::Relaxed),
+      total_patch_files: progress.patched_files.2.load(Ordering::Relaxed),
+    };
+    unsafe { callback(user_data, &snapshot) };
+  }
+}
@@ -0,0 +1,15 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Appends `extension` to the file name of `path`, e.g. `UDK.exe` and `bck` returns `UDK.exe.bck`
+pub(crate) fn append_extension(path: &Path, extension: &str) -> PathBuf {
+  let mut path: OsString = path.as_os_str().to_owned();
+  path.push(".");
+  path.push(extension);
+  path.into()
+}
+
+/// Returns the path of the backup file belonging to `path`
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+  append_extension(path, "bck")
+}
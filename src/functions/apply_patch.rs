@@ -1,38 +1,39 @@
-use crate::structures::Error;
+use crate::structures::{Error, Executor};
 use std::fs::DirBuilder;
-use crate::functions::get_hash;
+use std::path::PathBuf;
+use crate::functions::{append_extension, get_hash};
 use tracing::{info, instrument};
 
 /// Applies the vcdiff patch file to the target file
-#[instrument]
-pub(crate) async fn apply_patch(target_path: String, target_hash: String, delta_path: String) -> Result<(), Error> {
-  let mut dir_path = target_path.clone();
-  dir_path.truncate(target_path.rfind('/').ok_or_else(|| Error::None(format!("{} contains no /", target_path)))?);
+#[instrument(skip(executor))]
+pub(crate) async fn apply_patch(target_path: PathBuf, target_hash: String, delta_path: PathBuf, executor: &Executor) -> Result<(), Error> {
+  let dir_path = target_path.parent().ok_or_else(|| Error::None(format!("{} has no parent directory", target_path.display())))?;
   // Create directory incase it does not exist
   DirBuilder::new().recursive(true).create(dir_path)?;
 
-  let target_path_clone = target_path.clone();
-
-  tokio::task::Builder::new().name(&format!("apply_patch {}", target_hash)).spawn_blocking(move || {
-    if std::fs::File::open(&target_path_clone).is_ok() {
+  executor.spawn_blocking(&format!("apply_patch {}", target_hash), move || {
+    let target = target_path.to_str().ok_or_else(|| Error::None(format!("{} is not valid UTF-8", target_path.display())))?;
+    let delta = delta_path.to_str().ok_or_else(|| Error::None(format!("{} is not valid UTF-8", delta_path.display())))?;
+    if std::fs::File::open(&target_path).is_ok() {
       // If the patch_entry is a delta
-      let source_path = format!("{}.vcdiff_src", &target_path_clone);
+      let source_path = append_extension(&target_path, "vcdiff_src");
+      let source = source_path.to_str().ok_or_else(|| Error::None(format!("{} is not valid UTF-8", source_path.display())))?;
 
-      info!("Patching delta target file: {}, from file {} using the file {}", &target_path_clone, &source_path, &delta_path);
+      info!("Patching delta target file: {}, from file {} using the file {}", target, source, delta);
 
-      std::fs::rename(&target_path_clone, &source_path)?;
-      xdelta::decode_file(Some(&source_path), &delta_path, &target_path_clone);
+      std::fs::rename(&target_path, &source_path)?;
+      xdelta::decode_file(Some(source), delta, target);
       std::fs::remove_file(&source_path)?;
     } else {
       // If the patch_entry is a full
-      info!("Patching full target file: {}, using the file {}", &target_path_clone, &delta_path);
+      info!("Patching full target file: {}, using the file {}", target, delta);
 
-      xdelta::decode_file(None, &delta_path, &target_path_clone);
+      xdelta::decode_file(None, delta, target);
     }
     let hash = get_hash(&target_path)?;
     if hash != target_hash {
-      return Err(Error::HashMismatch(target_path.clone(), hash, target_hash.clone()));
+      return Err(Error::HashMismatch(target_path.display().to_string(), hash, target_hash.clone()));
     }
     Ok::<(), Error>(())
-  })?.await?
-}
\ No newline at end of file
+  }).await?
+}
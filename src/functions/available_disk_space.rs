@@ -0,0 +1,11 @@
+use std::path::Path;
+
+use crate::structures::Error;
+
+/// Returns the free space in bytes of the disk holding `path`
+///
+/// `path` does not have to exist yet, e.g. on a fresh install, the nearest existing ancestor is used instead.
+pub(crate) fn available_disk_space(path: &Path) -> Result<u64, Error> {
+  let existing = path.ancestors().find(|ancestor| std::fs::metadata(ancestor).is_ok()).unwrap_or(path);
+  Ok(fs2::available_space(existing)?)
+}
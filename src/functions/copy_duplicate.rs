@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use tracing::{info, instrument, warn};
+
+use crate::structures::{DeduplicationMode, Error, Executor};
+
+/// Places the already patched `source` at `target`, replacing whatever is at `target`
+#[instrument(skip(executor))]
+pub(crate) async fn copy_duplicate(source: PathBuf, target: PathBuf, mode: DeduplicationMode, executor: &Executor) -> Result<(), Error> {
+  executor.spawn_blocking(&format!("copy_duplicate {}", target.display()), move || {
+    if let Some(parent) = target.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::symlink_metadata(&target).is_ok() {
+      std::fs::remove_file(&target)?;
+    }
+    if mode == DeduplicationMode::HardLink {
+      match std::fs::hard_link(&source, &target) {
+        Ok(()) => {
+          info!("Hard-linked {} to {}", target.display(), source.display());
+          return Ok(());
+        },
+        Err(e) => warn!("Hard-linking {} to {} failed, copying instead: {}", target.display(), source.display(), e)
+      }
+    }
+    info!("Copying {} to {}", source.display(), target.display());
+    std::fs::copy(&source, &target)?;
+    Ok::<(), Error>(())
+  }).await?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::structures::TempDir;
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn hard_links_and_falls_back_to_copying() {
+    use std::os::unix::fs::MetadataExt;
+
+    let temp_dir = TempDir::new("copy_duplicate");
+    let source = temp_dir.path().join("source.u");
+    std::fs::write(&source, b"content").unwrap();
+    copy_duplicate(source.clone(), temp_dir.path().join("Maps/linked.u"), DeduplicationMode::HardLink, &Executor::default()).await.unwrap();
+    assert_eq!(std::fs::metadata(&source).unwrap().nlink(), 2);
+
+    // Hard links can not cross filesystems, /dev/shm is a separate one on most Linux systems
+    let Ok(other_filesystem) = std::fs::metadata("/dev/shm") else { return };
+    if other_filesystem.dev() == std::fs::metadata(temp_dir.path()).unwrap().dev() {
+      return;
+    }
+    let other_dir = TempDir::new_in(std::path::Path::new("/dev/shm"), "copy_duplicate");
+    let target = other_dir.path().join("copied.u");
+    copy_duplicate(source.clone(), target.clone(), DeduplicationMode::HardLink, &Executor::default()).await.unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), b"content");
+    assert_eq!(std::fs::metadata(&target).unwrap().nlink(), 1);
+  }
+}
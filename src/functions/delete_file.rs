@@ -1,9 +1,11 @@
+use std::path::PathBuf;
+
 use tracing::instrument;
 
 use crate::Error;
 
 #[instrument]
-pub fn delete_file(file: String) -> Result<(), Error> {
+pub fn delete_file(file: PathBuf) -> Result<(), Error> {
     std::fs::remove_file(file)?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::functions::{available_disk_space, backup_path};
+use crate::structures::{DiskSpace, DownloadEntry, Error};
+
+/// Estimates the disk space needed to download and apply `downloads` in `game_location`
+///
+/// Patched files are assumed to be as large as their full patch file or, for deltas, their source file.
+/// Partially downloaded patch files only count for what is left to download.
+/// With a `disk_space_budget` no more than the budget is downloaded at the same time, so the downloads are capped at it.
+pub(crate) fn disk_space_requirement<'a>(game_location: &Path, downloads: impl IntoIterator<Item = &'a DownloadEntry>, disk_space_budget: Option<u64>) -> Result<DiskSpace, Error> {
+  let mut download_paths = HashSet::new();
+  let mut target_hashes = HashSet::new();
+  let mut disk_space = DiskSpace::default();
+  let mut largest_delta = 0;
+
+  for download_entry in downloads {
+    if download_paths.insert(download_entry.download_path.clone()) {
+      let downloaded = std::fs::metadata(&download_entry.download_path).map(|metadata| metadata.len()).unwrap_or(0);
+      disk_space.downloads += download_entry.download_size.saturating_sub(downloaded);
+    }
+    // Content shared by several targets is only patched once
+    if !target_hashes.insert(download_entry.target_hash.clone()) {
+      continue;
+    }
+    match delta_space(download_entry) {
+      Some(delta_space) => largest_delta = largest_delta.max(delta_space),
+      None => disk_space.patching += download_entry.download_size,
+    }
+  }
+  disk_space.patching += largest_delta;
+  if let Some(disk_space_budget) = disk_space_budget {
+    disk_space.downloads = disk_space.downloads.min(disk_space_budget);
+  }
+  disk_space.available = available_disk_space(game_location)?;
+  Ok(disk_space)
+}
+
+/// The disk space patching `download_entry` takes on top of its patch file, None when there is no file to patch from
+///
+/// The file patched from, or its backup, is kept until the patched file is complete, which is assumed to be at least as large.
+pub(crate) fn delta_space(download_entry: &DownloadEntry) -> Option<u64> {
+  let source_size = std::fs::metadata(&download_entry.target_path)
+    .or_else(|_| std::fs::metadata(backup_path(&download_entry.target_path)))
+    .map(|metadata| metadata.len())
+    .ok()?;
+  Some(source_size + source_size.max(download_entry.download_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+
+    fn download_entry(game_location: &Path, download: &str, download_size: u64, target: &str, target_hash: &str) -> DownloadEntry {
+        DownloadEntry {
+            mirror_path: String::new(),
+            download_path: game_location.join("patcher").join(download),
+            download_size,
+            download_hash: download.to_string(),
+            target_path: game_location.join(target),
+            target_hash: target_hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn counts_remaining_downloads_new_files_and_largest_delta() {
+        let temp_dir = TempDir::new("disk_space");
+        let game_location = temp_dir.path();
+        std::fs::create_dir_all(game_location.join("patcher")).unwrap();
+        std::fs::write(game_location.join("patcher").join("DELTA"), vec![0u8; 30]).unwrap();
+        std::fs::write(game_location.join("existing"), vec![0u8; 500]).unwrap();
+
+        let downloads = vec![
+            download_entry(&game_location, "DELTA", 100, "existing", "A"),
+            download_entry(&game_location, "FULL", 1000, "new", "B"),
+            download_entry(&game_location, "FULL", 1000, "duplicate", "B"),
+        ];
+        let disk_space = disk_space_requirement(&game_location, &downloads, None).unwrap();
+        assert_eq!(disk_space.downloads, 70 + 1000);
+        assert_eq!(disk_space.patching, 1000 + 500 + 500);
+        assert_eq!(disk_space_requirement(&game_location, &downloads, Some(200)).unwrap().downloads, 200);
+    }
+}
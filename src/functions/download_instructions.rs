@@ -1,23 +1,30 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use crate::{Progress, pausable::{FutureContext, PausableTrait}, structures::{Mirrors, Instruction}, Error};
+use crate::{Progress, pausable::{FutureContext, PausableTrait}, structures::{Mirrors, Instruction, ValidationMode}, Error};
 
 use super::{parse_instructions, retrieve_instructions};
 
-pub(crate) async fn download_instructions(mut mirrors: Mirrors, instructions_hash: &str, progress: Progress, progress_callback: Box<dyn Fn(&Progress) + Send>, context: Arc<FutureContext>) -> Result<(Vec<Instruction>, Box<dyn Fn(&Progress) + Send>), Error> {
-    progress.set_current_action("Testing mirrors!".to_string())?;
-    progress_callback(&progress);
-    mirrors.test_mirrors().await?;
+pub(crate) async fn download_instructions(mut mirrors: Mirrors, instructions_hash: &str, progress: Progress, progress_callback: Box<dyn Fn(&Progress) + Send>, context: Arc<FutureContext>, validation_mode: ValidationMode, retain_in: Option<PathBuf>) -> Result<(Vec<Instruction>, Box<dyn Fn(&Progress) + Send>), Error> {
+    if !mirrors.tested {
+      progress.set_current_action("Testing mirrors!".to_string())?;
+      progress_callback(&progress);
+      mirrors.test_mirrors().await?;
+    }
     
     progress.set_current_action("Downloading instructions file!".to_string())?;
     progress_callback(&progress);
     
     // Download Instructions.json
     let instructions = retrieve_instructions(instructions_hash, &mirrors).pausable(context.clone()).await?;
+    if let Some(retain_in) = retain_in {
+      std::fs::create_dir_all(&retain_in)?;
+      std::fs::write(retain_in.join("instructions.json"), instructions.as_bytes())?;
+    }
     
     progress.set_current_action("Parsing instructions file!".to_string())?;
     progress_callback(&progress);
     
     // Parse Instructions.json
-    Ok((parse_instructions(instructions)?, progress_callback))
+    Ok((parse_instructions(instructions, validation_mode)?, progress_callback))
 }
\ No newline at end of file
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use crate::structures::Error;
+
+/// Makes sure `target` does not resolve to a location outside of `software_location`, e.g. through a symlinked directory
+///
+/// Symlinks are followed even when what they point to does not exist, so a dangling link out of the software location is rejected as well.
+pub(crate) fn ensure_inside_location(software_location: &Path, target: &Path) -> Result<(), Error> {
+  let root = match software_location.canonicalize() {
+    Ok(root) => root,
+    // Nothing exists yet, so nothing can be symlinked
+    Err(_) => return Ok(()),
+  };
+  match resolve_existing(target, MAX_SYMLINKS)? {
+    Some(resolved) if !resolved.starts_with(&root) => Err(Error::UnsafePath(target.to_string_lossy().to_string(), "resolves outside of the software location")),
+    Some(_) | None => Ok(()),
+  }
+}
+
+/// The most symlinks followed by hand while resolving a single path
+const MAX_SYMLINKS: u8 = 40;
+
+/// Resolves the deepest part of `path` that already exists, None if nothing of it exists
+///
+/// A dangling symlink can not be canonicalized, so its target is resolved instead.
+fn resolve_existing(path: &Path, symlinks_left: u8) -> Result<Option<PathBuf>, Error> {
+  let mut existing = path;
+  while std::fs::symlink_metadata(existing).is_err() {
+    existing = match existing.parent() {
+      Some(parent) => parent,
+      None => return Ok(None),
+    };
+  }
+  match existing.canonicalize() {
+    Ok(resolved) => Ok(Some(resolved)),
+    Err(_) if std::fs::symlink_metadata(existing)?.file_type().is_symlink() => {
+      if symlinks_left == 0 {
+        return Err(Error::UnsafePath(path.to_string_lossy().to_string(), "has too many levels of symbolic links"));
+      }
+      // A relative link is relative to the directory holding it, joining an absolute link replaces the path
+      let link = std::fs::read_link(existing)?;
+      let link_target = existing.parent().map(|parent| parent.join(&link)).unwrap_or(link);
+      resolve_existing(&link_target, symlinks_left - 1)
+    },
+    Err(e) => Err(e.into()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_escapes() {
+        let temp_dir = TempDir::new("ensure_inside");
+        let base = temp_dir.path();
+        let software_location = base.join("game");
+        let outside = base.join("outside");
+        std::fs::create_dir_all(software_location.join("UDKGame")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, software_location.join("Binaries")).unwrap();
+
+        assert!(ensure_inside_location(&software_location, &software_location.join("UDKGame/CookedPC/map.udk")).is_ok());
+        assert!(matches!(ensure_inside_location(&software_location, &software_location.join("Binaries/Win64/UDK.exe")), Err(Error::UnsafePath(_, _))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_dangling_symlink_escapes() {
+        let temp_dir = TempDir::new("ensure_inside_dangling");
+        let base = temp_dir.path();
+        let software_location = base.join("game");
+        std::fs::create_dir_all(software_location.join("UDKGame")).unwrap();
+        std::os::unix::fs::symlink(base.join("outside").join("missing.ini"), software_location.join("UDKGame/Default.ini")).unwrap();
+        std::os::unix::fs::symlink("../UDKGame/missing.ini", software_location.join("UDKGame/Local.ini")).unwrap();
+        std::os::unix::fs::symlink("Loop.ini", software_location.join("UDKGame/Loop.ini")).unwrap();
+
+        assert!(matches!(ensure_inside_location(&software_location, &software_location.join("UDKGame/Default.ini")), Err(Error::UnsafePath(_, _))));
+        assert!(ensure_inside_location(&software_location, &software_location.join("UDKGame/Local.ini")).is_ok());
+        assert!(matches!(ensure_inside_location(&software_location, &software_location.join("UDKGame/Loop.ini")), Err(Error::UnsafePath(_, _))));
+    }
+}
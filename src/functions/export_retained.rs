@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use tracing::info;
+
+use crate::structures::Error;
+
+/// Copies the retained patch files in `location` to `destination`, adding the `10kb_file` that mirrors are tested with
+///
+/// `destination` can be served by any static HTTP server and used as a mirror. Returns the amount of files exported.
+pub(crate) fn export_retained(location: &Path, destination: &Path) -> Result<u64, Error> {
+  std::fs::create_dir_all(destination)?;
+  let exported = copy_tree(location, destination)?;
+  std::fs::write(destination.join("10kb_file"), vec![0u8; 10_000])?;
+  info!("Exported {} retained files to {}", exported, destination.display());
+  Ok(exported)
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<u64, Error> {
+  let mut copied = 0;
+  for entry in std::fs::read_dir(from)? {
+    let entry = entry?;
+    let destination = to.join(entry.file_name());
+    if entry.file_type()?.is_dir() {
+      std::fs::create_dir_all(&destination)?;
+      copied += copy_tree(&entry.path(), &destination)?;
+    } else if std::fs::metadata(&destination).is_err() {
+      // Patch files are named after their content, so an existing file never has to be replaced
+      if std::fs::hard_link(entry.path(), &destination).is_err() {
+        std::fs::copy(entry.path(), &destination)?;
+      }
+      copied += 1;
+    }
+  }
+  Ok(copied)
+}
@@ -3,6 +3,7 @@ use tracing::{Instrument, instrument};
 use tracing::{info, error};
 use tokio::sync::Mutex;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
@@ -14,17 +15,17 @@ use futures::StreamExt;
 use futures::TryStreamExt;
 use futures::FutureExt;
 
-use crate::functions::delete_file;
+use crate::functions::{backup_path, delete_file, delta_space, disk_space_requirement, plan_actions};
 use crate::functions::determine_parts_to_download;
 use crate::pausable::{PausableTrait, FutureContext};
-use crate::structures::{DownloadEntry, Instruction};
+use crate::structures::{ContentStore, DeduplicationMode, DiskBudget, DownloadEntry, Event, Executor, FileHashes, Instruction, ValidationMode};
 use crate::structures::FilePart;
 use crate::structures::{Mirrors, Progress, Action};
-use crate::functions::apply_patch;
+use crate::functions::{apply_patch, copy_duplicate, move_file};
 
 
-pub(crate) async fn flow(mirrors: Mirrors, game_location: &String, instructions: Vec<Instruction>, progress: Progress, progress_callback: Box<dyn Fn(&Progress) + Send>, context: Arc<FutureContext>) -> Result<Box<dyn Fn(&Progress) + Send>, Error> {
-  progress.set_instructions_amount(instructions.len().try_into().expect("Somehow we have more than 2^64 instructions, colour me impressed"));
+pub(crate) async fn flow(mirrors: Mirrors, game_location: &Path, instructions: Vec<Instruction>, progress: Progress, progress_callback: Box<dyn Fn(&Progress) + Send>, context: Arc<FutureContext>, validation_mode: ValidationMode, case_insensitive: bool, deduplication_mode: DeduplicationMode, content_store: Option<ContentStore>, retain_in: Option<PathBuf>, disk_space_budget: Option<u64>, executor: Executor) -> Result<Box<dyn Fn(&Progress) + Send>, Error> {
+  progress.set_instructions_amount(instructions.len() as u64);
   progress.set_current_action("Validating, Downloading, Patching!".to_string())?;
   progress_callback(&progress);
 
@@ -33,27 +34,52 @@ pub(crate) async fn flow(mirrors: Mirrors, game_location: &String, instructions:
   let report_progress_clone = report_progress.clone();
 
   let future = async move {
+    let mut ticks = 0u64;
     loop {
       if report_progress_clone.load(Ordering::Relaxed) == false {
         break;
       }
       tokio::time::sleep(Duration::from_millis(250)).instrument(tracing::info_span!("Progress callback sleep")).await;
       progress_callback(&repeated_progress);
+      ticks += 1;
+      if ticks % 4 == 0 {
+        repeated_progress.emit(Event::Progress(repeated_progress.snapshot()));
+      }
     }
     info!("Done reporting download/patching progress!");
     progress_callback
   }.instrument(tracing::info_span!("Progress callback loop"));
-  let handle = tokio::runtime::Handle::current();
-  let progress_handle = tokio::task::Builder::new().name("Progress loop").spawn_on(future, &handle)?.instrument(tracing::info_span!("Progress callback loop"));
-  let game_location_clone = game_location.clone();
-  let actions = futures::stream::iter(instructions).map(move |instruction| instruction.determine_action(game_location_clone.clone())).buffer_unordered(1);
-
-  // Increment the progress and filter out Action::Nothing
+  let progress_handle = executor.spawn("Progress loop", future).instrument(tracing::info_span!("Progress callback loop"));
+
+  // Every file is hashed without changing anything first, so a lack of disk space fails the patch before any file is touched
+  let hashes = FileHashes::default();
+  let planned_actions = plan_actions(game_location, &instructions, case_insensitive, Some(&progress), &hashes, &executor).await;
+  let downloads = planned_actions.iter().filter_map(|action| match action { Ok(Action::Download(download_entry)) => Some(download_entry), _ => None });
+  let disk_space_check = disk_space_requirement(game_location, downloads, disk_space_budget).and_then(|disk_space| {
+    info!("Update requires {} bytes, {} bytes are available", disk_space.required(), disk_space.available);
+    disk_space.check()
+  });
+  if let Err(e) = disk_space_check {
+    report_progress.store(false, Ordering::Relaxed);
+    return Err(e);
+  }
+  progress.set_current_action("Downloading, Patching!".to_string())?;
+
+  // Files that are up to date without a backup are left alone unless they go into the content store, the others are determined again from the same hashes while downloading, this time restoring and removing files
+  let instructions : Vec<Instruction> = instructions.into_iter().zip(planned_actions).filter(|(instruction, action)| {
+    content_store.is_some() || !matches!(action, Ok(Action::Nothing)) || std::fs::metadata(backup_path(&instruction.location(game_location, case_insensitive))).is_ok()
+  }).map(|(instruction, _)| instruction).collect();
+  let game_location_clone = game_location.to_path_buf();
+  let content_store_clone = content_store.clone();
+  // Count the files to patch and filter out Action::Nothing
   let progress_clone = progress.clone();
-
-  let actions = actions
+  let progress_determine = progress.clone();
+  let executor_determine = executor.clone();
+  let actions = futures::stream::iter(instructions).map(move |instruction| {
+    let path = instruction.path.clone();
+    instruction.determine_action(game_location_clone.clone(), case_insensitive, content_store_clone.clone(), false, Some(progress_determine.clone()), hashes.clone(), executor_determine.clone()).map(move |result| result.map_err(|e| (path, e)))
+  }).buffer_unordered(1)
   .inspect_ok(move |action| {
-    progress_clone.increment_processed_instructions();
     if let Action::Download(_) = action {
       progress_clone.add_to_be_patched();
     } 
@@ -62,33 +88,84 @@ pub(crate) async fn flow(mirrors: Mirrors, game_location: &String, instructions:
 
   let delete_file_tasks : Vec<Pin<Box<dyn futures::Future<Output = Result<(), Error>> + Send + Sync>>> = vec![];
   let (sender, receiver) = futures::channel::mpsc::unbounded();
-  let tracker_lock : Arc<Mutex<HashMap<String, (Vec<crate::structures::DownloadEntry>, Vec<u64>)>>> = Arc::new(Mutex::new(HashMap::new()));
+  let tracker_lock : Arc<Mutex<HashMap<PathBuf, (Vec<crate::structures::DownloadEntry>, Vec<u64>)>>> = Arc::new(Mutex::new(HashMap::new()));
+  
+  // Targets that share their content with an earlier target, keyed by the target hash
+  let duplicates_lock : Arc<Mutex<HashMap<String, Vec<PathBuf>>>> = Arc::new(Mutex::new(HashMap::new()));
   
   let (patching_sender, mut patching_receiver) = futures::channel::mpsc::unbounded();
 
-  let actions_fut = verify_files(sender, game_location.clone(), actions, progress.clone(), patching_sender.clone(), tracker_lock.clone(), delete_file_tasks, mirrors.clone());
-  let actions_handle = tokio::task::Builder::new().name("Verification loop").spawn_on(actions_fut.pausable(context.clone()), &handle)?;
+  let disk_budget = disk_space_budget.map(DiskBudget::new);
+  let actions_fut = verify_files(sender, game_location.to_path_buf(), actions, progress.clone(), patching_sender.clone(), tracker_lock.clone(), duplicates_lock.clone(), delete_file_tasks, mirrors.clone(), validation_mode, disk_budget.clone(), executor.clone());
+  let actions_handle = executor.spawn("Verification loop", actions_fut.pausable(context.clone()));
 
-  let downloads_fut = download_files(receiver, progress.clone(), tracker_lock.clone(), patching_sender).instrument(tracing::info_span!("Download loop"));
+  let downloads_fut = download_files(receiver, game_location.to_path_buf(), progress.clone(), tracker_lock.clone(), patching_sender, validation_mode, disk_budget.clone(), executor.clone()).instrument(tracing::info_span!("Download loop"));
 
   let progress_clone = progress.clone();
-  let patching_fut = actions_handle.then(|validation_result| async move {
-    loop {
-      if let Some(patching_entry) = patching_receiver.next().await {
-        info!("Patching target file: {}, using the file {}", &patching_entry.target_path, &patching_entry.download_path);
-        apply_patch(patching_entry.target_path, patching_entry.target_hash, patching_entry.download_path).await?;
-        progress_clone.increment_completed_patches();
-      } else {
-        info!("Done patching files!");
-        break;
+  let retain_in_clone = retain_in.clone();
+  let game_location_patching = game_location.to_path_buf();
+  let executor_patching = executor.clone();
+  // Patching starts as soon as the first file is downloaded, while the remaining actions are still being determined
+  let patching_fut = async move {
+    let mut patched = HashMap::new();
+    let mut retained = HashMap::new();
+    let patching_result = async {
+      loop {
+        if let Some(patching_entry) = patching_receiver.next().await {
+          info!("Patching target file: {}, using the file {}", patching_entry.target_path.display(), patching_entry.download_path.display());
+          apply_patch(patching_entry.target_path.clone(), patching_entry.target_hash.clone(), patching_entry.download_path.clone(), &executor_patching).await?;
+          if let Some(content_store) = content_store.clone() {
+            let entry = patching_entry.clone();
+            executor_patching.spawn_blocking(&format!("Storing {}", &entry.target_hash), move || {
+              content_store.insert(&entry.download_path, &entry.download_hash)?;
+              content_store.insert(&entry.target_path, &entry.target_hash)
+            }).await??;
+          }
+          if let Some(disk_budget) = &disk_budget {
+            // Each patch file belongs to a single target hash, so nothing else needs it anymore
+            std::fs::remove_file(&patching_entry.download_path)?;
+            disk_budget.release(&patching_entry.download_path)?;
+          } else {
+            retained.insert(patching_entry.download_path, patching_entry.mirror_path);
+          }
+          progress_clone.emit(Event::FilePatched { path: relative_display(&game_location_patching, &patching_entry.target_path), hash: patching_entry.target_hash.clone() });
+          patched.insert(patching_entry.target_hash, patching_entry.target_path);
+          progress_clone.increment_completed_patches();
+        } else {
+          info!("Done patching files!");
+          break;
+        }
       }
+      Ok::<(), Error>(())
+    }.await;
+    if let (Err(_), Some(disk_budget)) = (&patching_result, &disk_budget) {
+      // Nothing will be released anymore, stop verification from waiting on the budget
+      disk_budget.close();
+    }
+    patching_result?;
+    actions_handle.await??;
+
+    // Every target hash has been patched once, place it at the remaining targets
+    let duplicates = std::mem::take(&mut *duplicates_lock.lock().await);
+    copy_duplicates(duplicates, &patched, &game_location_patching, &progress_clone, deduplication_mode, validation_mode, &executor_patching).await?;
+    // Move the patch files into mirror layout, now that nothing needs them anymore
+    if let Some(retain_in) = retain_in_clone {
+      for (download_path, mirror_path) in retained {
+        let destination = retain_in.join(&mirror_path);
+        if std::fs::metadata(&destination).is_err() {
+          move_file(&download_path, &destination)?;
+        }
+      }
+    }
+    if let Some(content_store) = content_store {
+      executor_patching.spawn_blocking("Evicting from content store", move || content_store.evict()).await??;
     }
-    validation_result?
-  }.instrument(tracing::info_span!("Patching loop")));
+    Ok::<(), Error>(())
+  }.instrument(tracing::info_span!("Patching loop"));
 
   info!("Gonna wait for patching and downloading to be done");
 
-  let (patching_result, downloads_result) = futures::join!(tokio::task::Builder::new().name("Actions/Patching loop").spawn_on(patching_fut.pausable(context.clone()), &handle)?.instrument(tracing::info_span!("Patching loop")), tokio::task::Builder::new().name("Download loop").spawn_on(downloads_fut.pausable(context.clone()), &handle)?);
+  let (patching_result, downloads_result) = futures::join!(executor.spawn("Actions/Patching loop", patching_fut.pausable(context.clone())).instrument(tracing::info_span!("Patching loop")), executor.spawn("Download loop", downloads_fut.pausable(context.clone())));
   
   info!("Patching and downloading done, telling progress to quit");
 
@@ -118,24 +195,46 @@ pub(crate) async fn flow(mirrors: Mirrors, game_location: &String, instructions:
 
   info!("Set progress (Cleaning up files)");
 
-  std::fs::remove_dir_all(format!("{}patcher", &game_location))?;
+  let patcher_folder = game_location.join("patcher");
+  match retain_in {
+    // Keep the retained patch files that live inside of the patcher folder
+    Some(retain_in) if retain_in.starts_with(&patcher_folder) => {
+      for entry in std::fs::read_dir(&patcher_folder)? {
+        let entry = entry?;
+        if retain_in.starts_with(entry.path()) {
+          continue;
+        }
+        if entry.file_type()?.is_dir() {
+          std::fs::remove_dir_all(entry.path())?;
+        } else {
+          std::fs::remove_file(entry.path())?;
+        }
+      }
+    },
+    _ => std::fs::remove_dir_all(patcher_folder)?,
+  }
 
   Ok(progress_callback)
 }
 
 #[instrument(skip(sender, actions, progress, delete_file_tasks))]
 async fn verify_files(
-  sender: UnboundedSender<Pin<Box<dyn futures::Future<Output = Result<(FilePart, Vec<u8>), Error>> + Send>>>,
-  game_location: String,
-  mut actions: impl StreamExt<Item = Result<Action, Error>> + Unpin,
+  sender: UnboundedSender<Pin<Box<dyn futures::Future<Output = Result<(FilePart, Vec<u8>), (PathBuf, Error)>> + Send>>>,
+  game_location: PathBuf,
+  mut actions: impl StreamExt<Item = Result<Action, (PathBuf, Error)>> + Unpin,
   progress: Progress,
   patching_sender: UnboundedSender<DownloadEntry>,
-  tracker_lock: Arc<Mutex<HashMap<String, (Vec<crate::structures::DownloadEntry>, Vec<u64>)>>>,
+  tracker_lock: Arc<Mutex<HashMap<PathBuf, (Vec<crate::structures::DownloadEntry>, Vec<u64>)>>>,
+  duplicates_lock: Arc<Mutex<HashMap<String, Vec<PathBuf>>>>,
   mut delete_file_tasks: Vec<Pin<Box<dyn futures::Future<Output = Result<(), Error>> + Send + Sync>>>,
-  mirrors: Mirrors
+  mirrors: Mirrors,
+  validation_mode: ValidationMode,
+  disk_budget: Option<DiskBudget>,
+  executor: Executor
 ) -> Result<(), Error> {
-  let patcher_folder = format!("{}patcher", &game_location);
+  let patcher_folder = game_location.join("patcher");
   std::fs::DirBuilder::new().recursive(true).create(patcher_folder)?;
+  let mut failures = Vec::new();
 
   loop {
     if let Some(action) = actions.next().await {
@@ -143,13 +242,23 @@ async fn verify_files(
         info!("action: {:#?}", action);
         match action {
             Action::Download(download_entry) => {
+              // Content that is already scheduled for another target only has to be patched once
+              let mut duplicates = duplicates_lock.lock().await;
+              if let Some(targets) = duplicates.get_mut(&download_entry.target_hash) {
+                info!("{} shares its content with an earlier target, it will be copied after patching", download_entry.target_path.display());
+                targets.push(download_entry.target_path);
+                continue;
+              }
+              duplicates.insert(download_entry.target_hash.clone(), Vec::new());
+              drop(duplicates);
+
               let mut exists = false;
               let mut tracker = tracker_lock.lock().await;
               if let Some((download_entries, parts)) = tracker.get_mut(&download_entry.download_path) {
                 if parts.len() == 0 {
                   info!("Ey, can start patchin this file: {:#?}", &download_entry);
                   progress.add_ready_to_patch();
-                  patching_sender.unbounded_send(download_entry.clone()).expect("Closed or sum shit");
+                  patching_sender.unbounded_send(download_entry.clone())?;
                 } else {
                   download_entries.push(download_entry.clone());
                 }
@@ -161,6 +270,11 @@ async fn verify_files(
                 continue;
               }
 
+              // Wait for earlier patch files to be patched and removed before allocating another one, counting the files it patches like the disk space check does
+              if let Some(disk_budget) = &disk_budget {
+                let patching_space = delta_space(&download_entry).unwrap_or(download_entry.download_size);
+                disk_budget.acquire(&download_entry.download_path, download_entry.download_size + patching_space).await?;
+              }
               let (download_location, parts) = determine_parts_to_download(&download_entry.download_path, &download_entry.download_hash, download_entry.download_size)?;
               if parts.len() == 0 {
                 let f = std::fs::OpenOptions::new().read(true).write(true).open(&download_entry.download_path)?;
@@ -168,11 +282,14 @@ async fn verify_files(
                 drop(f);
                 info!("Ey, can start patchin this file: {:#?}", &download_entry);
                 progress.add_ready_to_patch();
-                patching_sender.unbounded_send(download_entry.clone()).expect("Closed or sum shit");
+                patching_sender.unbounded_send(download_entry.clone())?;
               } else {
                 progress.add_download(parts.iter().map(|part| part.to - part.from).sum());
                 // add parts to be downloaded
-                parts.iter().for_each(|part| sender.unbounded_send(Box::pin(part.clone().download(mirrors.clone(), download_entry.mirror_path.clone(), progress.clone()))).expect("Channel closed or something"));
+                for part in parts.iter() {
+                  let target_path = download_entry.target_path.clone();
+                  sender.unbounded_send(Box::pin(part.clone().download(mirrors.clone(), download_entry.mirror_path.clone(), progress.clone(), executor.clone()).map(move |result| result.map_err(|e| (target_path, e)))))?;
+                }
                 let mut tracker = tracker_lock.lock().await;
                 let mut vec = Vec::new();
                 vec.push(download_entry);
@@ -181,11 +298,23 @@ async fn verify_files(
                 // when parts are downloaded, patch file
               }
             },
-            Action::Delete(file) => delete_file_tasks.push(Box::pin(async move { delete_file(file) })),
+            Action::Delete(file) => {
+              let progress = progress.clone();
+              let game_location = game_location.clone();
+              delete_file_tasks.push(Box::pin(async move {
+                delete_file(file.clone())?;
+                progress.increment_deleted_files();
+                progress.emit(Event::FileRemoved { path: relative_display(&game_location, &file) });
+                Ok(())
+              }))
+            },
             Action::Nothing => {},
         };
-      } else if let Err(e) = action {
-        error!("Processing file into action failed: {:#?}", e);
+      } else if let Err((path, e)) = action {
+        error!("Processing file {} into action failed: {:#?}", path.display(), e);
+        progress.emit(Event::FileFailed { path: relative_display(&game_location, &path), error: e.to_string() });
+        progress.add_skipped_file(format!("Skipped {}: {}", relative_display(&game_location, &path), e));
+        failures.push((path.display().to_string(), e));
       }
     } else {
       info!("Done verifying files!");
@@ -194,17 +323,27 @@ async fn verify_files(
   }
   drop(sender);
   drop(patching_sender);
+  // Files that are no longer part of the software are removed once everything else has been scheduled
+  futures::future::try_join_all(delete_file_tasks).await?;
+  if validation_mode == ValidationMode::Strict && !failures.is_empty() {
+    return Err(Error::FailedInstructions(failures));
+  }
   Ok::<(), Error>(())
 }
 
 #[instrument(skip(receiver, progress_original))]
 async fn download_files(
-  receiver: UnboundedReceiver<Pin<Box<dyn futures::Future<Output = Result<(FilePart, Vec<u8>), Error>> + Send>>>,
+  receiver: UnboundedReceiver<Pin<Box<dyn futures::Future<Output = Result<(FilePart, Vec<u8>), (PathBuf, Error)>> + Send>>>,
+  game_location: PathBuf,
   progress_original: Progress,
-  tracker_lock: Arc<Mutex<HashMap<String, (Vec<crate::structures::DownloadEntry>, Vec<u64>)>>>,
+  tracker_lock: Arc<Mutex<HashMap<PathBuf, (Vec<crate::structures::DownloadEntry>, Vec<u64>)>>>,
   patching_sender_original: UnboundedSender<DownloadEntry>,
+  validation_mode: ValidationMode,
+  disk_budget: Option<DiskBudget>,
+  executor: Executor,
 ) -> Result<(), Error> {
   let mut buffered_receiver = receiver.buffer_unordered(10);
+  let mut failures : Vec<(String, Error)> = Vec::new();
   loop {
     if let Some(action) = buffered_receiver.next().await {
       let tracker_lock_clone = tracker_lock.clone();
@@ -212,31 +351,44 @@ async fn download_files(
       let patching_sender = patching_sender_original.clone();
 
       if let Ok((part, buffer)) = action {
-        tokio::task::Builder::new().name(&format!("Handling part {} of {}", part.part_byte, part.file)).spawn(async move {
+        let executor_part = executor.clone();
+        executor.spawn(&format!("Handling part {} of {}", part.part_byte, part.file.display()), async move {
             info!("Part downloaded: {:#?}", part);
-            part.write_to_file(buffer).await?;
+            part.write_to_file(buffer, &executor_part).await?;
             //progress.increment_downloaded_bytes(part.to - part.from);
     
             let mut tracker = tracker_lock_clone.lock().await;
-            let (download_entries, parts) = tracker.get_mut(&part.file).ok_or_else(|| Error::None(format!("No tracker entry found for: {}", &part.file)))?;
-            parts.remove(parts.binary_search(&part.part_byte).expect("Could not find the part_byte"));
+            let (download_entries, parts) = tracker.get_mut(&part.file).ok_or_else(|| Error::None(format!("No tracker entry found for: {}", part.file.display())))?;
+            let index = parts.binary_search(&part.part_byte).map_err(|_| Error::None(format!("Part {} of {} was not being tracked", &part.part_byte, part.file.display())))?;
+            parts.remove(index);
             if parts.len() == 0 {
               let f = std::fs::OpenOptions::new().read(true).write(true).open(&download_entries[0].download_path)?;
               f.set_len(download_entries[0].download_size)?;
               drop(f);
               progress.increment_completed_downloads();
+              progress.increment_downloaded_patch_files(download_entries[0].mirror_path.starts_with("delta/"));
     
-              download_entries.iter().for_each(|download_entry| {
+              for download_entry in download_entries.iter() {
                 info!("Ey, can start patchin this file: {:#?}", &download_entry);
                 progress.add_ready_to_patch();
-                patching_sender.unbounded_send(download_entry.clone()).expect("Closed or sum shit");
-              });
+                patching_sender.unbounded_send(download_entry.clone())?;
+              }
             }
             drop(tracker);
           Ok::<(), Error>(())
-        })?.await??;
-      } else if let Err(e) = action {
-        error!("Downloading FilePart failed: {:#?}", e);
+        }).await??;
+      } else if let Err((path, e)) = action {
+        error!("Downloading a FilePart of {} failed: {:#?}", path.display(), e);
+        // A file with several failed parts is only reported once
+        if !failures.iter().any(|(failed, _)| failed == &path.display().to_string()) {
+          progress_original.emit(Event::FileFailed { path: relative_display(&game_location, &path), error: e.to_string() });
+          progress_original.add_skipped_file(format!("Skipped {}: {}", relative_display(&game_location, &path), e));
+          failures.push((path.display().to_string(), e));
+        }
+        if let Some(disk_budget) = &disk_budget {
+          // The file will never be patched and released, stop verification from waiting on the budget
+          disk_budget.close();
+        }
       }
     } else {
       info!("Done downloading files!");
@@ -244,5 +396,145 @@ async fn download_files(
     }
   }
   drop(patching_sender_original);
+  if validation_mode == ValidationMode::Strict && !failures.is_empty() {
+    return Err(Error::FailedInstructions(failures));
+  }
   Ok::<(), Error>(())
-}
\ No newline at end of file
+}
+
+/// Places the content patched for every target hash at the other targets that share it
+///
+/// Content that was never patched fails the patch, or with `Lenient` validation its other targets are skipped as well.
+async fn copy_duplicates(duplicates: HashMap<String, Vec<PathBuf>>, patched: &HashMap<String, PathBuf>, game_location: &Path, progress: &Progress, deduplication_mode: DeduplicationMode, validation_mode: ValidationMode, executor: &Executor) -> Result<(), Error> {
+  for (target_hash, targets) in duplicates {
+    let source = match patched.get(&target_hash) {
+      Some(source) => source,
+      None if validation_mode == ValidationMode::Lenient => {
+        for target in targets {
+          progress.add_skipped_file(format!("Skipped {}: content with hash {} was never patched", relative_display(game_location, &target), &target_hash));
+        }
+        continue;
+      },
+      None => return Err(Error::None(format!("Content with hash {} was never patched", &target_hash))),
+    };
+    for target in targets {
+      progress.add_ready_to_patch();
+      copy_duplicate(source.clone(), target.clone(), deduplication_mode, executor).await?;
+      progress.emit(Event::FilePatched { path: relative_display(game_location, &target), hash: target_hash.clone() });
+      progress.increment_completed_patches();
+    }
+  }
+  Ok(())
+}
+
+/// Displays `path` relative to `game_location` when it is inside of it
+fn relative_display(game_location: &Path, path: &Path) -> String {
+  path.strip_prefix(game_location).unwrap_or(path).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::functions::get_hash;
+  use crate::structures::TempDir;
+
+  #[tokio::test]
+  async fn deletes_files_that_are_no_longer_versioned() {
+    let temp_dir = TempDir::new("flow_delete");
+    let game_location = temp_dir.path();
+    let removed = game_location.join("removed.u");
+    std::fs::write(&removed, b"old").unwrap();
+
+    let (sender, _receiver) = futures::channel::mpsc::unbounded();
+    let (patching_sender, _patching_receiver) = futures::channel::mpsc::unbounded();
+    let progress = Progress::new();
+    let actions = futures::stream::iter(vec![Ok(Action::Delete(removed.clone()))]);
+    verify_files(
+      sender, game_location.to_path_buf(), actions, progress.clone(), patching_sender,
+      Arc::new(Mutex::new(HashMap::new())), Arc::new(Mutex::new(HashMap::new())), vec![],
+      Mirrors { mirrors: vec![], tested: true }, ValidationMode::Strict, None, Executor::default()
+    ).await.unwrap();
+
+    assert!(!removed.exists());
+    assert_eq!(progress.finish_report().unwrap().deleted_files, 1);
+  }
+
+  #[tokio::test]
+  async fn failed_downloads_fail_strict_runs() {
+    let temp_dir = TempDir::new("flow_failed_download");
+    let target = temp_dir.path().join("broken.u");
+    let download = |validation_mode| {
+      let (sender, receiver) = futures::channel::mpsc::unbounded::<Pin<Box<dyn futures::Future<Output = Result<(FilePart, Vec<u8>), (PathBuf, Error)>> + Send>>>();
+      let (patching_sender, _patching_receiver) = futures::channel::mpsc::unbounded();
+      // Both parts of the file fail
+      for _ in 0..2 {
+        sender.unbounded_send(Box::pin(futures::future::ready(Err((target.clone(), Error::InvalidStatus("404 Not Found".to_string())))))).unwrap();
+      }
+      drop(sender);
+      let progress = Progress::new();
+      let result = download_files(receiver, temp_dir.path().to_path_buf(), progress.clone(), Arc::new(Mutex::new(HashMap::new())), patching_sender, validation_mode, None, Executor::default());
+      (result, progress)
+    };
+
+    let (result, _) = download(ValidationMode::Strict);
+    match result.await {
+      Err(Error::FailedInstructions(failures)) => assert_eq!(failures.len(), 1),
+      other => panic!("Expected FailedInstructions, got {:?}", other),
+    }
+    let (result, progress) = download(ValidationMode::Lenient);
+    result.await.unwrap();
+    assert_eq!(progress.finish_report().unwrap().skipped_files, 1);
+  }
+
+  #[tokio::test]
+  async fn shared_content_is_patched_once_and_copied() {
+    let temp_dir = TempDir::new("flow_duplicates");
+    let game_location = temp_dir.path();
+    std::fs::create_dir_all(game_location.join("patcher")).unwrap();
+    // A complete patch file is not downloaded again
+    let download_path = game_location.join("patcher").join("FULL");
+    std::fs::write(&download_path, b"content").unwrap();
+    let download_entry = |target: &str| DownloadEntry {
+      mirror_path: "full/CONTENT".to_string(),
+      download_path: download_path.clone(),
+      download_size: 7,
+      download_hash: get_hash(&download_path).unwrap(),
+      target_path: game_location.join(target),
+      target_hash: "CONTENT".to_string(),
+    };
+
+    let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+    let (patching_sender, mut patching_receiver) = futures::channel::mpsc::unbounded();
+    let duplicates_lock = Arc::new(Mutex::new(HashMap::new()));
+    let progress = Progress::new();
+    let actions = futures::stream::iter(vec![Ok(Action::Download(download_entry("first.u"))), Ok(Action::Download(download_entry("Maps/second.u")))]);
+    verify_files(
+      sender, game_location.to_path_buf(), actions, progress.clone(), patching_sender,
+      Arc::new(Mutex::new(HashMap::new())), duplicates_lock.clone(), vec![],
+      Mirrors { mirrors: vec![], tested: true }, ValidationMode::Strict, None, Executor::default()
+    ).await.unwrap();
+
+    assert!(receiver.next().await.is_none());
+    let patching_entry = patching_receiver.next().await.unwrap();
+    assert_eq!(patching_entry.target_path, game_location.join("first.u"));
+    assert!(patching_receiver.next().await.is_none());
+
+    std::fs::write(&patching_entry.target_path, b"patched").unwrap();
+    let patched = HashMap::from([(patching_entry.target_hash, patching_entry.target_path)]);
+    let duplicates = std::mem::take(&mut *duplicates_lock.lock().await);
+    copy_duplicates(duplicates, &patched, game_location, &progress, DeduplicationMode::Copy, ValidationMode::Strict, &Executor::default()).await.unwrap();
+    assert_eq!(std::fs::read(game_location.join("Maps/second.u")).unwrap(), b"patched");
+  }
+
+  #[tokio::test]
+  async fn lenient_validation_skips_copies_of_content_that_was_never_patched() {
+    let temp_dir = TempDir::new("flow_unpatched_duplicates");
+    let duplicates = || HashMap::from([("CONTENT".to_string(), vec![temp_dir.path().join("second.u")])]);
+    let progress = Progress::new();
+
+    assert!(copy_duplicates(duplicates(), &HashMap::new(), temp_dir.path(), &progress, DeduplicationMode::Copy, ValidationMode::Strict, &Executor::default()).await.is_err());
+    copy_duplicates(duplicates(), &HashMap::new(), temp_dir.path(), &progress, DeduplicationMode::Copy, ValidationMode::Lenient, &Executor::default()).await.unwrap();
+    assert_eq!(progress.finish_report().unwrap().skipped_files, 1);
+    assert!(std::fs::metadata(temp_dir.path().join("second.u")).is_err());
+  }
+}
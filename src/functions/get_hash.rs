@@ -1,9 +1,9 @@
-use std::{fs::OpenOptions, io::Read};
+use std::{fs::OpenOptions, io::Read, path::Path};
 use sha2::{Sha256, Digest};
 use crate::structures::Error;
 
 /// Opens a file and calculates it's SHA256 hash
-pub(crate) fn get_hash(file_path: &str) -> Result<String, Error> {
+pub(crate) fn get_hash(file_path: &Path) -> Result<String, Error> {
 	let mut file = OpenOptions::new().read(true).open(file_path)?;
 	let mut hasher = Sha256::new();
 	let mut read : usize;
@@ -14,4 +14,4 @@ pub(crate) fn get_hash(file_path: &str) -> Result<String, Error> {
 	drop(file);
 	drop(buffer);
 	Ok(hex::encode_upper(hasher.finalize()))
-}
\ No newline at end of file
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::info;
+
+use crate::functions::append_extension;
+use crate::structures::{Error, Instruction, InstallState};
+
+/// File, relative to the software location, that records the version the install was last patched to
+pub const INSTALL_STATE_FILE : &str = "install_state.json";
+
+/// Records that the install at `game_location` is on `version`, replacing the state file at once
+pub(crate) fn write_install_state(game_location: &Path, version: &str, instructions_hash: &str, instructions: &[Instruction]) -> Result<InstallState, Error> {
+  let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| Error::None(format!("System time is before the unix epoch: {}", e)))?.as_secs();
+  let state = InstallState {
+    version: version.to_string(),
+    instructions_hash: instructions_hash.to_string(),
+    timestamp,
+    patcher_version: env!("CARGO_PKG_VERSION").to_string(),
+    files: instructions.iter().filter_map(|instruction| Some((instruction.path.clone(), instruction.newest_hash.clone()?))).collect(),
+  };
+  let path = game_location.join(INSTALL_STATE_FILE);
+  let temporary = append_extension(&path, "tmp");
+  std::fs::write(&temporary, serde_json::to_string_pretty(&state)?)?;
+  std::fs::rename(&temporary, &path)?;
+  info!("Recorded version {} in {}", version, path.display());
+  Ok(state)
+}
+
+/// Reads the state file of the install at `game_location`, None if it has none
+pub(crate) fn read_install_state(game_location: &Path) -> Result<Option<InstallState>, Error> {
+  match std::fs::read_to_string(game_location.join(INSTALL_STATE_FILE)) {
+    Ok(text) => Ok(Some(serde_json::from_str(&text)?)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(e.into()),
+  }
+}
+
+/// Removes the state file before the install is changed, so an interrupted patch is never mistaken for a finished one
+pub(crate) fn invalidate_install_state(game_location: &Path) -> Result<(), Error> {
+  match std::fs::remove_file(game_location.join(INSTALL_STATE_FILE)) {
+    Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+
+    #[test]
+    fn writes_reads_and_invalidates_the_state() {
+        let temp_dir = TempDir::new("install_state");
+        let game_location = temp_dir.path();
+        assert_eq!(read_install_state(&game_location).unwrap(), None);
+
+        let instruction = |path: &str, newest_hash: Option<&str>| Instruction {
+            path: path.into(),
+            previous_hash: None,
+            newest_hash: newest_hash.map(str::to_string),
+            full_vcdiff_hash: None,
+            delta_vcdiff_hash: None,
+            full_vcdiff_size: 0,
+            delta_vcdiff_size: 0,
+            has_delta: false,
+        };
+        let written = write_install_state(&game_location, "5.89", "ABCD", &[instruction("a.txt", Some("1234")), instruction("removed.txt", None)]).unwrap();
+        assert_eq!(written.files.len(), 1);
+        assert_eq!(read_install_state(&game_location).unwrap(), Some(written));
+
+        invalidate_install_state(&game_location).unwrap();
+        invalidate_install_state(&game_location).unwrap();
+        assert_eq!(read_install_state(&game_location).unwrap(), None);
+    }
+}
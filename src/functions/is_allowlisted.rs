@@ -0,0 +1,38 @@
+use std::path::Path;
+
+/// Patterns of files that are never removed as unversioned, unless overridden on the `PatcherBuilder`
+pub const DEFAULT_UNVERSIONED_ALLOWLIST : [&str; 6] = [
+  "**/InstallInfo.xml",
+  "UDKGame/Config/*.ini",
+  "UDKGame/Logs/**",
+  "UDKGame/ScreenShots/**",
+  "UDKGame/SaveData/**",
+  "**/*.log",
+];
+
+/// Checks whether `relative_path` matches one of the patterns in `allowlist`
+pub(crate) fn is_allowlisted(relative_path: &Path, allowlist: &[glob::Pattern], case_sensitive: bool) -> bool {
+  let options = glob::MatchOptions {
+    case_sensitive,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+  };
+  allowlist.iter().any(|pattern| pattern.matches_path_with(relative_path, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_allowlist() {
+        let allowlist : Vec<glob::Pattern> = DEFAULT_UNVERSIONED_ALLOWLIST.iter().map(|pattern| glob::Pattern::new(pattern).unwrap()).collect();
+        assert!(is_allowlisted(Path::new("InstallInfo.xml"), &allowlist, true));
+        assert!(is_allowlisted(Path::new("UDKGame/Config/UDKGame.ini"), &allowlist, true));
+        assert!(is_allowlisted(Path::new("UDKGame/ScreenShots/2021/shot.png"), &allowlist, true));
+        assert!(!is_allowlisted(Path::new("UDKGame/Config/Custom/UDKGame.ini"), &allowlist, true));
+        assert!(!is_allowlisted(Path::new("UDKGame/CookedPC/Maps/CNC-Field.udk"), &allowlist, true));
+        assert!(!is_allowlisted(Path::new("udkgame/config/UDKGame.ini"), &allowlist, true));
+        assert!(is_allowlisted(Path::new("udkgame/config/UDKGame.ini"), &allowlist, false));
+    }
+}
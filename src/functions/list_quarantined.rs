@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::functions::QUARANTINE_DIRECTORY;
+use crate::structures::{Error, QuarantinedFile};
+
+/// Lists every file in the quarantine of `game_location`, oldest first
+pub(crate) fn list_quarantined(game_location: &Path) -> Result<Vec<QuarantinedFile>, Error> {
+  let mut quarantined = Vec::new();
+  for (quarantined_at, session) in quarantine_sessions(game_location)? {
+    collect_files(&session, &session, &mut |path, location| quarantined.push(QuarantinedFile { quarantined_at, path, location }))?;
+  }
+  Ok(quarantined)
+}
+
+/// Lists the timestamped quarantine folders of `game_location`, oldest first
+pub(crate) fn quarantine_sessions(game_location: &Path) -> Result<Vec<(std::time::SystemTime, PathBuf)>, Error> {
+  let quarantine = game_location.join(QUARANTINE_DIRECTORY);
+  let mut sessions = Vec::new();
+  if std::fs::metadata(&quarantine).is_err() {
+    return Ok(sessions);
+  }
+  for entry in std::fs::read_dir(&quarantine)? {
+    let entry = entry?;
+    // Sessions started in the same second carry a counter after the seconds, e.g. `1700000000-1`
+    if let Some(seconds) = entry.file_name().to_str().and_then(|name| name.split('-').next()?.parse::<u64>().ok()) {
+      sessions.push((UNIX_EPOCH + Duration::from_secs(seconds), entry.path()));
+    }
+  }
+  sessions.sort();
+  Ok(sessions)
+}
+
+fn collect_files(session: &Path, dir: &Path, found: &mut impl FnMut(PathBuf, PathBuf)) -> Result<(), Error> {
+  for entry in std::fs::read_dir(dir)? {
+    let entry = entry?;
+    if entry.file_type()?.is_dir() {
+      collect_files(session, &entry.path(), found)?;
+    } else {
+      found(entry.path().strip_prefix(session)?.to_path_buf(), entry.path());
+    }
+  }
+  Ok(())
+}
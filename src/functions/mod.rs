@@ -17,7 +17,7 @@ mod get_hash;
 pub(crate) use get_hash::get_hash as get_hash;
 
 mod remove_unversioned;
-pub(crate) use remove_unversioned::remove_unversioned as remove_unversioned;
+pub(crate) use remove_unversioned::{find_unversioned, remove_unversioned};
 
 mod read_dir;
 pub(crate) use read_dir::read_dir as read_dir;
@@ -32,4 +32,54 @@ mod determine_parts_to_download;
 pub(crate) use determine_parts_to_download::determine_parts_to_download as determine_parts_to_download;
 
 mod download_instructions;
-pub(crate) use download_instructions::download_instructions as download_instructions;
\ No newline at end of file
+pub(crate) use download_instructions::download_instructions as download_instructions;
+
+mod normalize_manifest_path;
+pub(crate) use normalize_manifest_path::normalize_manifest_path as normalize_manifest_path;
+
+mod ensure_inside_location;
+pub(crate) use ensure_inside_location::ensure_inside_location as ensure_inside_location;
+
+mod append_extension;
+pub(crate) use append_extension::{append_extension, backup_path};
+
+mod is_allowlisted;
+pub use is_allowlisted::DEFAULT_UNVERSIONED_ALLOWLIST as DEFAULT_UNVERSIONED_ALLOWLIST;
+pub(crate) use is_allowlisted::is_allowlisted as is_allowlisted;
+
+mod quarantine_file;
+pub use quarantine_file::QUARANTINE_DIRECTORY as QUARANTINE_DIRECTORY;
+pub(crate) use quarantine_file::{new_quarantine_session, quarantine_file};
+
+mod list_quarantined;
+pub(crate) use list_quarantined::{list_quarantined, quarantine_sessions};
+
+mod restore_quarantined;
+pub(crate) use restore_quarantined::restore_quarantined as restore_quarantined;
+
+mod purge_quarantine;
+pub(crate) use purge_quarantine::purge_quarantine as purge_quarantine;
+
+mod resolve_path_case;
+pub(crate) use resolve_path_case::resolve_path_case as resolve_path_case;
+
+mod copy_duplicate;
+pub(crate) use copy_duplicate::copy_duplicate as copy_duplicate;
+
+mod move_file;
+pub(crate) use move_file::move_file as move_file;
+
+mod export_retained;
+pub(crate) use export_retained::export_retained as export_retained;
+mod available_disk_space;
+pub(crate) use available_disk_space::available_disk_space as available_disk_space;
+
+mod disk_space_requirement;
+pub(crate) use disk_space_requirement::{delta_space, disk_space_requirement};
+
+mod plan_actions;
+pub(crate) use plan_actions::plan_actions as plan_actions;
+
+mod install_state;
+pub use install_state::INSTALL_STATE_FILE as INSTALL_STATE_FILE;
+pub(crate) use install_state::{invalidate_install_state, read_install_state, write_install_state};
@@ -0,0 +1,15 @@
+use std::path::Path;
+
+use crate::structures::Error;
+
+/// Moves `from` to `to`, falling back to copying when they are on different filesystems
+pub(crate) fn move_file(from: &Path, to: &Path) -> Result<(), Error> {
+  if let Some(parent) = to.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  if std::fs::rename(from, to).is_err() {
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)?;
+  }
+  Ok(())
+}
@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use crate::structures::Error;
+
+/// Converts a Windows style manifest path into a normalised relative path, e.g. `Binaries\.\Win64\UDK.exe` returns the relative path `Binaries/Win64/UDK.exe` using the platform's separators
+///
+/// Paths that could point outside of the software location (absolute paths, drive letters, `..` components) are rejected.
+pub(crate) fn normalize_manifest_path(path: &str) -> Result<PathBuf, Error> {
+  let unsafe_path = |reason| Err(Error::UnsafePath(path.to_string(), reason));
+  if path.contains('\0') {
+    return unsafe_path("contains a NUL character");
+  }
+  if path.contains(':') {
+    return unsafe_path("contains a drive letter or alternate data stream");
+  }
+  let path_with_slashes = path.replace('\\', "/");
+  if path_with_slashes.starts_with('/') {
+    return unsafe_path("is absolute");
+  }
+  let mut components = Vec::new();
+  for component in path_with_slashes.split('/') {
+    match component {
+      "" | "." => {},
+      ".." => return unsafe_path("contains a parent directory component"),
+      component => components.push(component),
+    }
+  }
+  if components.is_empty() {
+    return unsafe_path("is empty");
+  }
+  Ok(components.iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_windows_paths() {
+        assert_eq!(normalize_manifest_path("Binaries\\.\\Win64\\\\UDK.exe").unwrap(), ["Binaries", "Win64", "UDK.exe"].iter().collect::<PathBuf>());
+    }
+
+    #[test]
+    fn rejects_escaping_paths() {
+        for path in ["..\\..\\Windows\\foo.dll", "UDKGame/../../foo.dll", "\\Windows\\foo.dll", "/etc/passwd", "C:\\Windows\\foo.dll", "C:foo.dll", "UDKGame\\foo.dll:stream", "", ".\\"] {
+            assert!(matches!(normalize_manifest_path(path), Err(Error::UnsafePath(_, _))), "{} was accepted", path);
+        }
+    }
+}
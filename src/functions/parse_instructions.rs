@@ -1,32 +1,75 @@
-use crate::structures::{Error, Instruction};
-use crate::traits::AsString;
+use std::convert::TryFrom;
+
+use crate::structures::{Error, Instruction, Manifest, ManifestEntry, ValidationMode};
 use tracing::error;
 
 
-pub(crate) fn parse_instructions(instructions: Box<String>) -> Result<Vec<Instruction>, Error> {
-    let instructions_data = match json::parse(&instructions) {
+pub(crate) fn parse_instructions(instructions: Box<String>, validation_mode: ValidationMode) -> Result<Vec<Instruction>, Error> {
+  let manifest = match serde_json::from_str::<Manifest>(&instructions) {
     Ok(result) => result,
     Err(e) => return Err(Error::InvalidJson(format!("instructions.json is invalid: {}", e), *instructions))
   };
-  let mut instructions = Vec::with_capacity(instructions_data.len());
-  instructions_data.into_inner().iter().for_each(|instruction| {
-    let mut closure = || -> Result<(), Error> {
-      instructions.push(Instruction {
-        path:                 instruction["Path"].as_string().replace("\\", "/"),
-        previous_hash:        instruction["OldHash"].as_string_option(),
-        newest_hash:          instruction["NewHash"].as_string_option(),
-        full_vcdiff_hash:     instruction["CompressedHash"].as_string_option(),
-        delta_vcdiff_hash:    instruction["DeltaHash"].as_string_option(),
-        full_vcdiff_size:     instruction["FullReplaceSize"].as_u64().ok_or_else(|| Error::None(format!("retrieve_instructions.rs: Could not cast JSON version_number as a usize, input was {}", instruction["FullReplaceSize"])))?,
-        delta_vcdiff_size:    instruction["DeltaSize"].as_u64().ok_or_else(|| Error::None(format!("retrieve_instructions.rs: Could not cast JSON version_number as a usize, input was {}", instruction["DeltaSize"])))?,
-        has_delta:            instruction["HasDelta"].as_bool().ok_or_else(|| Error::None(format!("retrieve_instructions.rs: Could not cast JSON version_number as a usize, input was {}", instruction["HasDelta"])))?
-      });
-      Ok(())
-    };
-    match closure() {
-      Ok(()) => {},
-      Err(e) => error!("Transforming instructions failed for instruction {}, with error: {}", instruction, e)
+  let entries = manifest.into_entries()?;
+  let mut instructions = Vec::with_capacity(entries.len());
+  let mut failures = Vec::new();
+  for entry in entries {
+    let path = entry["Path"].as_str().map(str::to_string).unwrap_or_else(|| entry.to_string());
+    let result = serde_json::from_value::<ManifestEntry>(entry).map_err(Error::from).and_then(Instruction::try_from);
+    match result {
+      Ok(instruction) => instructions.push(instruction),
+      Err(e) => {
+        error!("Transforming instructions failed for instruction {}, with error: {}", &path, e);
+        failures.push((path, e));
+      }
     };
-  });
+  }
+  if validation_mode == ValidationMode::Strict && !failures.is_empty() {
+    return Err(Error::FailedInstructions(failures));
+  }
   Ok(instructions)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTRUCTIONS: &str = r#"[
+      {"Path": "Binaries\\Win64\\UDK.exe", "OldHash": null, "NewHash": "FA1AFFF978325F8818CE3A559D67A58297D9154674DE7FD8EB03656D93104425", "CompressedHash": "1854E191B7DB2537CF1F27DBC512D0FED8C661329EC6BC8A0290BFB125CC12C0", "DeltaHash": null, "FullReplaceSize": 10, "DeltaSize": 0, "HasDelta": false},
+      {"Path": "Binaries\\Win64\\broken.dll", "OldHash": null, "NewHash": "FA1AFFF978325F8818CE3A559D67A58297D9154674DE7FD8EB03656D93104425", "CompressedHash": "1854E191B7DB2537CF1F27DBC512D0FED8C661329EC6BC8A0290BFB125CC12C0", "DeltaHash": null, "FullReplaceSize": "ten", "DeltaSize": 0, "HasDelta": false},
+      {"Path": "Binaries\\Win64\\delta.dll", "OldHash": "FA1AFFF978325F8818CE3A559D67A58297D9154674DE7FD8EB03656D93104425", "NewHash": "12A3F14FC43BB76C8E58DA0B0FE493C7DB360C1F029281AD6179F4E08C4D1A9E", "CompressedHash": "1854E191B7DB2537CF1F27DBC512D0FED8C661329EC6BC8A0290BFB125CC12C0", "DeltaHash": null, "FullReplaceSize": 10, "DeltaSize": 5, "HasDelta": true},
+      {"Path": "Binaries\\Win64\\short.dll", "OldHash": null, "NewHash": "FA1AFF", "CompressedHash": "1854E191B7DB2537CF1F27DBC512D0FED8C661329EC6BC8A0290BFB125CC12C0", "DeltaHash": null, "FullReplaceSize": 10, "DeltaSize": 0, "HasDelta": false}
+    ]"#;
+
+    #[test]
+    fn strict_mode_lists_failed_instructions() {
+        match parse_instructions(Box::new(INSTRUCTIONS.to_string()), ValidationMode::Strict) {
+            Err(Error::FailedInstructions(failures)) => {
+                let paths : Vec<&str> = failures.iter().map(|(path, _)| path.as_str()).collect();
+                assert_eq!(paths, vec!["Binaries\\Win64\\broken.dll", "Binaries\\Win64\\delta.dll", "Binaries\\Win64\\short.dll"]);
+            },
+            result => panic!("Expected FailedInstructions, got {:?}", result)
+        }
+    }
+
+    #[test]
+    fn lenient_mode_skips_failed_instructions() {
+        let instructions = parse_instructions(Box::new(INSTRUCTIONS.to_string()), ValidationMode::Lenient).unwrap();
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].path, ["Binaries", "Win64", "UDK.exe"].iter().collect::<std::path::PathBuf>());
+    }
+
+    #[test]
+    fn versioned_manifest() {
+        let manifest = format!(r#"{{"ManifestVersion": 2, "Instructions": {}}}"#, INSTRUCTIONS);
+        assert_eq!(parse_instructions(Box::new(manifest), ValidationMode::Lenient).unwrap().len(), 1);
+
+        let manifest = format!(r#"{{"ManifestVersion": 99, "Instructions": {}}}"#, INSTRUCTIONS);
+        assert!(matches!(parse_instructions(Box::new(manifest), ValidationMode::Lenient), Err(Error::UnsupportedManifestVersion(99))));
+    }
+
+    #[test]
+    fn sample_manifest_is_valid() {
+        let manifest = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/instructions.json")).unwrap();
+        assert_eq!(parse_instructions(Box::new(manifest), ValidationMode::Strict).unwrap().len(), 1246);
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+use crate::structures::{Action, Error, Executor, FileHashes, Instruction, Progress};
+
+/// Determines what every instruction would do to the files at `game_location`, without changing any of them
+///
+/// The content store is not consulted, so everything that is not on disk is assumed to be downloaded.
+/// The hashes end up in `hashes`, for patching the files afterwards without hashing them again.
+pub(crate) async fn plan_actions(game_location: &Path, instructions: &[Instruction], case_insensitive: bool, progress: Option<&Progress>, hashes: &FileHashes, executor: &Executor) -> Vec<Result<Action, (PathBuf, Error)>> {
+  let mut actions = Vec::with_capacity(instructions.len());
+  for instruction in instructions {
+    let path = instruction.path.clone();
+    actions.push(instruction.clone().determine_action(game_location.to_path_buf(), case_insensitive, None, true, None, hashes.clone(), executor.clone()).await.map_err(|e| (path, e)));
+    if let Some(progress) = progress {
+      progress.increment_processed_instructions();
+    }
+  }
+  actions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::functions::{backup_path, get_hash};
+  use crate::structures::TempDir;
+
+  #[tokio::test]
+  async fn leaves_backups_and_outdated_files_alone() {
+    let temp_dir = TempDir::new("plan_actions");
+    let restorable = temp_dir.path().join("restorable.u");
+    std::fs::write(backup_path(&restorable), b"newest").unwrap();
+    let outdated = temp_dir.path().join("outdated.u");
+    std::fs::write(&outdated, b"unknown").unwrap();
+    let newest_hash = get_hash(&backup_path(&restorable)).unwrap();
+
+    let instruction = |path: &str| Instruction {
+      path: path.into(),
+      previous_hash: None,
+      newest_hash: Some(newest_hash.clone()),
+      full_vcdiff_hash: Some("0".repeat(64)),
+      delta_vcdiff_hash: None,
+      full_vcdiff_size: 6,
+      delta_vcdiff_size: 0,
+      has_delta: false,
+    };
+    let progress = Progress::new();
+    let actions = plan_actions(temp_dir.path(), &[instruction("restorable.u"), instruction("outdated.u")], false, Some(&progress), &FileHashes::default(), &Executor::default()).await;
+
+    assert!(matches!(actions[0], Ok(Action::Nothing)));
+    assert!(matches!(actions[1], Ok(Action::Download(_))));
+    assert!(std::fs::metadata(backup_path(&restorable)).is_ok());
+    assert!(std::fs::metadata(&restorable).is_err());
+    assert_eq!(std::fs::read(&outdated).unwrap(), b"unknown");
+    assert_eq!(progress.finish_report().unwrap().verified_files, 2);
+  }
+}
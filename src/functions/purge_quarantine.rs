@@ -0,0 +1,19 @@
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use tracing::info;
+
+use crate::functions::quarantine_sessions;
+use crate::structures::Error;
+
+/// Removes every quarantine folder of `game_location` that is older than `max_age`
+pub(crate) fn purge_quarantine(game_location: &Path, max_age: Duration) -> Result<(), Error> {
+  let now = SystemTime::now();
+  for (quarantined_at, session) in quarantine_sessions(game_location)? {
+    if now.duration_since(quarantined_at).map(|age| age > max_age).unwrap_or(false) {
+      info!("Purging quarantine folder: {:?}", &session);
+      std::fs::remove_dir_all(&session)?;
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,55 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::info;
+
+use crate::structures::Error;
+
+/// Directory, relative to the software location, that holds quarantined files
+pub const QUARANTINE_DIRECTORY : &str = "quarantine";
+
+/// Creates a new quarantine folder for the current moment, named after the seconds since the unix epoch and a counter once that name is taken
+pub(crate) fn new_quarantine_session(game_location: &Path) -> Result<PathBuf, Error> {
+  let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| Error::None(format!("System time is before the unix epoch: {}", e)))?.as_secs();
+  let quarantine = game_location.join(QUARANTINE_DIRECTORY);
+  std::fs::create_dir_all(&quarantine)?;
+  let mut attempt = 0;
+  loop {
+    let session = match attempt {
+      0 => quarantine.join(timestamp.to_string()),
+      _ => quarantine.join(format!("{}-{}", timestamp, attempt)),
+    };
+    match std::fs::create_dir(&session) {
+      Ok(()) => return Ok(session),
+      Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+      Err(e) => return Err(e.into()),
+    }
+  }
+}
+
+/// Moves `path` into `session`, preserving its path relative to `game_location`
+pub(crate) fn quarantine_file(game_location: &Path, session: &Path, path: &Path) -> Result<(), Error> {
+  let destination = session.join(path.strip_prefix(game_location)?);
+  if let Some(parent) = destination.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  info!("Quarantining {:?} to {:?}", path, &destination);
+  std::fs::rename(path, destination)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::functions::quarantine_sessions;
+  use crate::structures::TempDir;
+
+  #[test]
+  fn sessions_in_the_same_second_do_not_collide() {
+    let temp_dir = TempDir::new("quarantine_sessions");
+    let first = new_quarantine_session(temp_dir.path()).unwrap();
+    let second = new_quarantine_session(temp_dir.path()).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(quarantine_sessions(temp_dir.path()).unwrap().len(), 2);
+  }
+}
@@ -1,30 +1,48 @@
-use crate::structures::{Directory, Error};
-use tracing::info;
+use std::path::{Path, PathBuf};
 
-/// This function iterates through `dir` and removes any files that aren't in `versioned_files`
+use crate::structures::{Error, PathIndex};
+use crate::functions::is_allowlisted;
+
+/// This function iterates through `dir` and collects any files and directories that aren't in `versioned_files` or matched by `allowlist` into `unversioned`
+///
+/// Returns true if everything inside of `dir` ended up in `unversioned`, in which case the caller can remove `dir` as a whole.
 pub fn read_dir(
-    dir: &std::path::Path,
-    versioned_files: &Directory,
-    renegadex_path: &std::path::PathBuf,
-  ) -> Result<(), Error> {
+    dir: &Path,
+    versioned_files: &PathIndex,
+    renegadex_path: &Path,
+    allowlist: &[glob::Pattern],
+    unversioned: &mut Vec<PathBuf>,
+  ) -> Result<bool, Error> {
+    let mut everything_unversioned = true;
     let files = std::fs::read_dir(dir)?;
     for file in files {
       let file = file?;
+      let relative_path = file.path().strip_prefix(renegadex_path)?.to_owned();
+      if is_allowlisted(&relative_path, allowlist, !versioned_files.case_insensitive) {
+        everything_unversioned = false;
+        continue;
+      }
       if file.file_type()?.is_dir()
       { // this file is a directory
-        if versioned_files.directory_exists(file.path().strip_prefix(&renegadex_path)?.to_owned()) {
-          read_dir(&file.path(), versioned_files, renegadex_path)?;
+        if versioned_files.directory_exists(&relative_path) {
+          read_dir(&file.path(), versioned_files, renegadex_path, allowlist, unversioned)?;
+          everything_unversioned = false;
         } else {
-          info!("Removing directory: {:?}", &file.path());
-          std::fs::remove_dir_all(&file.path())?;
+          let mut contents = Vec::new();
+          if read_dir(&file.path(), versioned_files, renegadex_path, allowlist, &mut contents)? {
+            unversioned.push(file.path());
+          } else {
+            unversioned.append(&mut contents);
+            everything_unversioned = false;
+          }
         }
       } else { // this is a file
-        if !versioned_files.file_exists(file.path().strip_prefix(&renegadex_path)?.to_owned())? {
-          info!("Removing file: {:?}", &file.path());
-          std::fs::remove_file(&file.path())?;
+        if versioned_files.file_exists(&relative_path) {
+          everything_unversioned = false;
+        } else {
+          unversioned.push(file.path());
         }
-        //doubt anything
       }
     }
-    Ok(())
-  }
\ No newline at end of file
+    Ok(everything_unversioned)
+  }
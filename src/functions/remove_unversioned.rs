@@ -1,59 +1,128 @@
-use crate::{structures::{Directory, Error, Instruction}, functions::read_dir, Progress};
+use crate::{structures::{Error, Event, Instruction, PathIndex, UnversionedMode}, functions::{new_quarantine_session, quarantine_file, read_dir, INSTALL_STATE_FILE, QUARANTINE_DIRECTORY}, Progress};
 use tracing::info;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// This function converts the instructions array to a Directory structure
-fn instructions_to_directory_info(instructions: &Vec<Instruction>, renegadex_path: &PathBuf) -> Result<Directory, Error> {
-  let mut versioned_files = Directory::new();
-  // build up directory structure based on instructions.json
+/// This function converts the instructions array to a PathIndex of versioned files
+fn instructions_to_path_index(instructions: &Vec<Instruction>, case_insensitive: bool) -> PathIndex {
+  let mut versioned_files = PathIndex::new(case_insensitive);
   for entry in instructions.iter() {
-    let mut path = &mut versioned_files;
-    let mut directory_iter = PathBuf::from(&entry.path).strip_prefix(&renegadex_path)?.to_path_buf();
-    directory_iter.pop();
-    for directory in directory_iter.iter() {
-      path = path.get_or_create_subdirectory(directory.to_owned())?;
-    }
-    //path should be the correct directory now.
-    //thus add file to path.files
     if entry.newest_hash.is_some() {
-      path.files.push(
-        PathBuf::from(&entry.path)
-          .strip_prefix(&renegadex_path)?
-          .to_path_buf(),
-      );
+      versioned_files.insert_file(&entry.path);
+    } else if let Some(parent) = entry.path.parent() {
+      // Directories of removed files are kept, as they were versioned before
+      versioned_files.insert_directory(parent);
     }
   }
-  Ok(versioned_files)
+  versioned_files
 }
 
+/// Lists the files and directories in `game_location` that would be removed by `remove_unversioned`, without removing anything
+pub(crate) fn find_unversioned(game_location: &Path, instructions: &Vec<Instruction>, allowlist: &[glob::Pattern], case_insensitive: bool) -> Result<Vec<PathBuf>, Error> {
+  let versioned_files = instructions_to_path_index(instructions, case_insensitive);
+  // Never treat the quarantine, the install state or the patcher's own folder as unversioned
+  let mut allowlist = allowlist.to_vec();
+  allowlist.push(glob::Pattern::new(&glob::Pattern::escape(QUARANTINE_DIRECTORY))?);
+  allowlist.push(glob::Pattern::new(&glob::Pattern::escape(INSTALL_STATE_FILE))?);
+  allowlist.push(glob::Pattern::new("patcher")?);
+  let mut unversioned = Vec::new();
+  if std::fs::metadata(game_location).is_ok() {
+    read_dir(game_location, &versioned_files, game_location, &allowlist, &mut unversioned)?;
+  }
+  Ok(unversioned)
+}
 
-pub(crate) async fn remove_unversioned(game_location: String, instructions: Vec<Instruction>, progress: Progress, progress_callback: Box<dyn Fn(&Progress) + Send>) -> Result<(), Error> {
-    let game_path = std::path::PathBuf::from(game_location.clone());
+pub(crate) async fn remove_unversioned(game_location: &Path, instructions: Vec<Instruction>, allowlist: &[glob::Pattern], mode: UnversionedMode, case_insensitive: bool, progress: Progress, progress_callback: Box<dyn Fn(&Progress) + Send>) -> Result<(), Error> {
     progress.set_current_action("Removing unknown files!".to_string())?;
     progress_callback(&progress);
 
-    let versioned_files = instructions_to_directory_info(&instructions, &game_path)?;
-
     // Create the game directory if it doesn't exist already
-    match std::fs::read_dir(game_location.clone()) {
+    match std::fs::read_dir(game_location) {
       Ok(_) => {}
-      Err(_) => std::fs::create_dir_all(game_location.clone())?,
+      Err(_) => std::fs::create_dir_all(game_location)?,
     };
 
-    // Iterate through the files and remove the unversioned
-    let files = std::fs::read_dir(game_location.clone())?;
-    for file in files {
-      let file = file?;
-      if file.file_type()?.is_dir() {
-        if versioned_files.directory_exists(file.path().strip_prefix(&game_path)?.to_owned()) {
-          read_dir(&file.path(), &versioned_files, &game_path)?;
-        } else {
-          info!("Remove directory: {:?}", &file.path());
-        }
+    // Remove the unversioned files and directories
+    let mut quarantine_session = None;
+    for path in find_unversioned(game_location, &instructions, allowlist, case_insensitive)? {
+      let relative_path = path.strip_prefix(game_location).unwrap_or(&path).display().to_string();
+      if mode == UnversionedMode::Quarantine {
+        // Only start a quarantine folder once there is something to put in it
+        let session = match &quarantine_session {
+          Some(session) => session,
+          None => quarantine_session.insert(new_quarantine_session(game_location)?),
+        };
+        quarantine_file(game_location, session, &path)?;
+        progress.emit(Event::FileQuarantined { path: relative_path });
+        continue;
+      } else if std::fs::symlink_metadata(&path)?.is_dir() {
+        info!("Removing directory: {:?}", &path);
+        std::fs::remove_dir_all(&path)?;
       } else {
-        info!("Remove file: {:?}", &file.path());
-        //doubt anything
+        info!("Removing file: {:?}", &path);
+        std::fs::remove_file(&path)?;
       }
+      progress.increment_deleted_files();
+      progress.emit(Event::FileRemoved { path: relative_path });
     }
     Ok(())
-  }
\ No newline at end of file
+  }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+    use crate::DEFAULT_UNVERSIONED_ALLOWLIST;
+
+    fn instruction(path: &str) -> Instruction {
+        Instruction {
+            path: PathBuf::from(path),
+            previous_hash: None,
+            newest_hash: Some("FA1AFFF978325F8818CE3A559D67A58297D9154674DE7FD8EB03656D93104425".to_string()),
+            full_vcdiff_hash: Some("1854E191B7DB2537CF1F27DBC512D0FED8C661329EC6BC8A0290BFB125CC12C0".to_string()),
+            delta_vcdiff_hash: None,
+            full_vcdiff_size: 0,
+            delta_vcdiff_size: 0,
+            has_delta: false,
+        }
+    }
+
+    #[test]
+    fn finds_unversioned_at_every_level() {
+        let temp_dir = TempDir::new("find_unversioned");
+        let game_location = temp_dir.path();
+        for file in ["InstallInfo.xml", "unknown.txt", "UDKGame/CookedPC/versioned.upk", "UDKGame/CookedPC/unknown.upk", "UDKGame/Config/UDKGame.ini", "UDKGame/CustomMaps/CNC-Custom.udk", "UDKGame/Logs/Launch.log"] {
+            let path = game_location.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        let allowlist : Vec<glob::Pattern> = DEFAULT_UNVERSIONED_ALLOWLIST.iter().map(|pattern| glob::Pattern::new(pattern).unwrap()).collect();
+
+        let mut unversioned = find_unversioned(&game_location, &vec![instruction("UDKGame/CookedPC/versioned.upk")], &allowlist, false).unwrap();
+        unversioned.sort();
+        assert_eq!(unversioned, vec![
+            game_location.join("UDKGame/CookedPC/unknown.upk"),
+            game_location.join("UDKGame/CustomMaps"),
+            game_location.join("unknown.txt"),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn quarantines_and_restores() {
+        let temp_dir = TempDir::new("quarantine");
+        let game_location = temp_dir.path();
+        let custom_map = game_location.join("UDKGame/CustomMaps/CNC-Custom.udk");
+        std::fs::create_dir_all(custom_map.parent().unwrap()).unwrap();
+        std::fs::write(&custom_map, b"map").unwrap();
+
+        remove_unversioned(&game_location, vec![], &[], UnversionedMode::Quarantine, false, Progress::new(), Box::new(|_| {})).await.unwrap();
+        assert!(std::fs::metadata(&custom_map).is_err());
+
+        let quarantined = crate::functions::list_quarantined(&game_location).unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].path, PathBuf::from("UDKGame/CustomMaps/CNC-Custom.udk"));
+
+        crate::functions::restore_quarantined(&game_location, &quarantined[0]).unwrap();
+        assert_eq!(std::fs::read(&custom_map).unwrap(), b"map");
+        assert!(std::fs::metadata(game_location.join(QUARANTINE_DIRECTORY)).is_err());
+    }
+}
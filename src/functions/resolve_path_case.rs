@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+
+/// Joins `relative` onto `root`, matching every component case-insensitively against what already exists on disk
+///
+/// Components that do not exist in any casing are kept as they are, e.g. `Binaries/Win64/UDK.exe` returns
+/// `root/binaries/win64/UDK.exe` if only `root/binaries/win64` exists.
+pub(crate) fn resolve_path_case(root: &Path, relative: &Path) -> PathBuf {
+  let mut resolved = root.to_path_buf();
+  let mut components = relative.iter();
+  while let Some(component) = components.next() {
+    let exact = resolved.join(component);
+    if std::fs::symlink_metadata(&exact).is_ok() {
+      resolved = exact;
+      continue;
+    }
+    let wanted = component.to_string_lossy().to_lowercase();
+    let existing = std::fs::read_dir(&resolved).ok().and_then(|entries| {
+      entries.filter_map(|entry| entry.ok()).map(|entry| entry.file_name()).find(|name| name.to_string_lossy().to_lowercase() == wanted)
+    });
+    match existing {
+      Some(name) => resolved.push(name),
+      None => {
+        // Nothing further down can exist either
+        resolved.push(component);
+        resolved.extend(components);
+        break;
+      }
+    }
+  }
+  resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+
+    #[test]
+    fn resolves_existing_casing() {
+        let temp_dir = TempDir::new("resolve_case");
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("binaries/win64")).unwrap();
+        std::fs::write(root.join("binaries/win64/udk.EXE"), b"").unwrap();
+
+        assert_eq!(resolve_path_case(&root, Path::new("Binaries/Win64/UDK.exe")), root.join("binaries/win64/udk.EXE"));
+        assert_eq!(resolve_path_case(&root, Path::new("Binaries/Win32/UDK.exe")), root.join("binaries/Win32/UDK.exe"));
+    }
+}
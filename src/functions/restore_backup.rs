@@ -1,7 +1,10 @@
+use std::path::Path;
+
 use crate::Error;
+use crate::functions::backup_path;
 
-pub fn restore_backup(path: &str) -> Result<(), Error> {
+pub fn restore_backup(path: &Path) -> Result<(), Error> {
     std::fs::remove_file(path)?;
-    std::fs::rename(format!("{}.bck", path), path)?;
+    std::fs::rename(backup_path(path), path)?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use tracing::info;
+
+use crate::functions::QUARANTINE_DIRECTORY;
+use crate::structures::{Error, QuarantinedFile};
+
+/// Moves a quarantined file back to its original location, refuses to overwrite an existing file
+pub(crate) fn restore_quarantined(game_location: &Path, file: &QuarantinedFile) -> Result<(), Error> {
+  let destination = game_location.join(&file.path);
+  if std::fs::symlink_metadata(&destination).is_ok() {
+    return Err(Error::IoError(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("{} already exists", destination.display()))));
+  }
+  if let Some(parent) = destination.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  info!("Restoring {:?} to {:?}", &file.location, &destination);
+  std::fs::rename(&file.location, &destination)?;
+
+  // Clean up directories that were emptied by the restore, up to and including the quarantine folder
+  let quarantine = game_location.join(QUARANTINE_DIRECTORY);
+  let mut directory = file.location.parent();
+  while let Some(dir) = directory {
+    if !dir.starts_with(&quarantine) || std::fs::remove_dir(dir).is_err() {
+      break;
+    }
+    directory = dir.parent();
+  }
+  Ok(())
+}
@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tracing::{info, warn};
+
+use crate::functions::{append_extension, get_hash};
+use crate::structures::{ContentStore, Error};
+
+impl ContentStore {
+  pub fn new(location: PathBuf, max_size: Option<u64>) -> Self {
+    Self {
+      location,
+      max_size,
+    }
+  }
+
+  fn path_of(&self, hash: &str) -> PathBuf {
+    self.location.join(hash.to_uppercase())
+  }
+
+  /// Copies the file with `hash` to `destination`, returns false if the store does not hold it
+  ///
+  /// The copy is verified before it replaces `destination`, corrupted entries are removed from the store.
+  pub fn fetch(&self, hash: &str, destination: &Path) -> Result<bool, Error> {
+    let cached = self.path_of(hash);
+    if std::fs::metadata(&cached).is_err() {
+      return Ok(false);
+    }
+    if let Some(parent) = destination.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    let temporary = append_extension(destination, "store_tmp");
+    std::fs::copy(&cached, &temporary)?;
+    if !get_hash(&temporary)?.eq_ignore_ascii_case(hash) {
+      warn!("Content store entry {} is corrupted, removing it", cached.display());
+      std::fs::remove_file(&temporary)?;
+      std::fs::remove_file(&cached)?;
+      return Ok(false);
+    }
+    std::fs::rename(&temporary, destination)?;
+    mark_used(&cached);
+    info!("Fetched {} from the content store", destination.display());
+    Ok(true)
+  }
+
+  /// Adds `source`, whose content has already been verified to match `hash`, to the store
+  pub fn insert(&self, source: &Path, hash: &str) -> Result<(), Error> {
+    let cached = self.path_of(hash);
+    if std::fs::metadata(&cached).is_ok() {
+      mark_used(&cached);
+      return Ok(());
+    }
+    std::fs::create_dir_all(&self.location)?;
+    // Copy under a temporary name first, so other installs never see a partial file
+    let temporary = append_extension(&cached, &format!("{}.tmp", std::process::id()));
+    std::fs::copy(source, &temporary)?;
+    std::fs::rename(&temporary, &cached)?;
+    Ok(())
+  }
+
+  /// Removes the least recently used files until the store fits in `max_size`
+  pub fn evict(&self) -> Result<(), Error> {
+    let max_size = match self.max_size {
+      Some(max_size) => max_size,
+      None => return Ok(()),
+    };
+    if std::fs::metadata(&self.location).is_err() {
+      return Ok(());
+    }
+    let mut entries = Vec::new();
+    let mut total_size = 0;
+    for entry in std::fs::read_dir(&self.location)? {
+      let entry = entry?;
+      let metadata = entry.metadata()?;
+      if metadata.is_file() {
+        total_size += metadata.len();
+        entries.push((metadata.modified()?, metadata.len(), entry.path()));
+      }
+    }
+    entries.sort();
+    for (_, size, path) in entries {
+      if total_size <= max_size {
+        break;
+      }
+      info!("Evicting {} from the content store", path.display());
+      std::fs::remove_file(&path)?;
+      total_size -= size;
+    }
+    Ok(())
+  }
+}
+
+/// Marks a store entry as recently used, entries of a store that is shared read-only simply keep their age
+fn mark_used(cached: &Path) {
+  if let Err(e) = std::fs::File::options().write(true).open(cached).and_then(|file| file.set_modified(SystemTime::now())) {
+    info!("Could not mark {} as recently used: {}", cached.display(), e);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+
+    #[test]
+    fn fetch_insert_and_evict() {
+        let temp_dir = TempDir::new("content_store");
+        let base = temp_dir.path();
+        let store = ContentStore::new(base.join("store"), Some(4));
+        let source = base.join("source.txt");
+        std::fs::write(&source, b"data").unwrap();
+        let hash = get_hash(&source).unwrap();
+
+        assert!(!store.fetch(&hash, &base.join("target.txt")).unwrap());
+        store.insert(&source, &hash).unwrap();
+        assert!(store.fetch(&hash, &base.join("game/target.txt")).unwrap());
+        assert_eq!(std::fs::read(base.join("game/target.txt")).unwrap(), b"data");
+
+        std::fs::write(&source, b"other").unwrap();
+        let other_hash = get_hash(&source).unwrap();
+        store.insert(&source, &other_hash).unwrap();
+        store.evict().unwrap();
+        assert!(std::fs::metadata(store.location.join(&hash)).is_err());
+    }
+}
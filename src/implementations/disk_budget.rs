@@ -0,0 +1,64 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+
+use crate::structures::{DiskBudget, Error};
+
+impl DiskBudget {
+  pub(crate) fn new(bytes: u64) -> Self {
+    let permits = Self::to_permits(bytes, Semaphore::MAX_PERMITS.min(u32::MAX as usize) as u32);
+    Self {
+      semaphore: Arc::new(Semaphore::new(permits as usize)),
+      permits,
+      acquired: Default::default(),
+    }
+  }
+
+  /// Rounds `bytes` up to KiB, a single file larger than the whole budget takes the whole budget so it can still be patched on its own
+  fn to_permits(bytes: u64, max: u32) -> u32 {
+    bytes.div_ceil(1024).min(max as u64).max(1) as u32
+  }
+
+  /// Waits until `bytes` fit in the budget and holds them for `patch_file` until it is released
+  pub(crate) async fn acquire(&self, patch_file: &Path, bytes: u64) -> Result<(), Error> {
+    let permits = Self::to_permits(bytes, self.permits);
+    let permit = self.semaphore.acquire_many(permits).await.map_err(|_| Error::ChannelClosed("disk budget"))?;
+    permit.forget();
+    self.acquired.lock()?.insert(patch_file.to_path_buf(), permits);
+    Ok(())
+  }
+
+  /// Returns what was acquired for `patch_file`, once it has been applied and removed
+  pub(crate) fn release(&self, patch_file: &Path) -> Result<(), Error> {
+    if let Some(permits) = self.acquired.lock()?.remove(patch_file) {
+      self.semaphore.add_permits(permits as usize);
+    }
+    Ok(())
+  }
+
+  /// Makes every waiting and future `acquire` fail, used when patching can not continue
+  pub(crate) fn close(&self) {
+    self.semaphore.close();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn acquire_waits_for_release() {
+        let budget = DiskBudget::new(10 * 1024);
+        budget.acquire(Path::new("A"), 8 * 1024).await.unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(50), budget.acquire(Path::new("B"), 4 * 1024)).await.is_err());
+        budget.release(Path::new("A")).unwrap();
+        budget.acquire(Path::new("B"), 4 * 1024).await.unwrap();
+        // Files larger than the budget take the whole budget
+        budget.release(Path::new("B")).unwrap();
+        budget.acquire(Path::new("C"), 1024 * 1024).await.unwrap();
+        budget.close();
+        assert!(budget.acquire(Path::new("D"), 1).await.is_err());
+    }
+}
@@ -0,0 +1,20 @@
+use crate::structures::{DiskSpace, Error};
+
+impl DiskSpace {
+  /// The total amount of bytes the update needs
+  pub fn required(&self) -> u64 {
+    self.downloads + self.patching
+  }
+
+  pub fn is_sufficient(&self) -> bool {
+    self.required() <= self.available
+  }
+
+  /// Returns `InsufficientDiskSpace` if the update does not fit on the disk
+  pub fn check(&self) -> Result<(), Error> {
+    if !self.is_sufficient() {
+      return Err(Error::InsufficientDiskSpace { required: self.required(), available: self.available });
+    }
+    Ok(())
+  }
+}
@@ -6,7 +6,16 @@ impl std::fmt::Display for Error {
   #[track_caller]
   #[inline(always)]
   fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-    write!(f,"{}", self)
+    match self {
+      Self::FailedInstructions(failures) => {
+        write!(f, "{} instruction(s) failed:", failures.len())?;
+        for (path, error) in failures {
+          write!(f, "\n  {}: {}", path, error)?;
+        }
+        Ok(())
+      },
+      _ => write!(f,"{:?}", self)
+    }
   }
 }
 
@@ -19,6 +28,15 @@ impl From<std::path::StripPrefixError> for Error {
   }
 }
 
+impl From<glob::PatternError> for Error {
+  #[track_caller]
+  #[inline(always)]
+  fn from(error: glob::PatternError) -> Self {
+    log_error(&error);
+    Self::InvalidPattern(error)
+  }
+}
+
 impl From<tokio::task::JoinError> for Error {
   #[track_caller]
   #[inline(always)]
@@ -60,9 +78,6 @@ impl<T> From<std::sync::PoisonError<std::sync::MutexGuard<'_, T>>> for Error {
   #[track_caller]
   #[inline(always)]
   fn from(error: std::sync::PoisonError<std::sync::MutexGuard<'_, T>>) -> Self {
-    use std::error::Error;
-    log_error(&error);
-    let error = error.source().unwrap();
     log_error(&error);
     Self::MutexPoisoned(error.to_string())
   }
@@ -99,15 +114,24 @@ impl From<Box<dyn std::error::Error + Sync + std::marker::Send>> for Error {
   #[track_caller]
   #[inline(always)]
   fn from(error: Box<dyn std::error::Error + Sync + std::marker::Send>) -> Self {
-    log_error(&error.source().unwrap());
+    log_error(error.as_ref());
     Self::DownloadError(error)
   }
 }
 
-impl From<json::Error> for Error {
+impl<T> From<futures::channel::mpsc::TrySendError<T>> for Error {
+  #[track_caller]
+  #[inline(always)]
+  fn from(error: futures::channel::mpsc::TrySendError<T>) -> Self {
+    log_error(&error.into_send_error());
+    Self::ChannelClosed("Receiving end of the channel was dropped")
+  }
+}
+
+impl From<serde_json::Error> for Error {
   #[track_caller]
   #[inline(always)]
-  fn from(error: json::Error) -> Self {
+  fn from(error: serde_json::Error) -> Self {
     log_error(&error);
     Self::JsonError(error)
   }
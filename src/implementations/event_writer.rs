@@ -0,0 +1,73 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::structures::{Event, EventWriter};
+
+#[derive(Serialize)]
+struct Line<'a> {
+  /// Milliseconds since the UNIX epoch
+  timestamp: u64,
+  #[serde(flatten)]
+  event: &'a Event,
+}
+
+impl EventWriter {
+  pub(crate) fn new(writer: Box<dyn Write + Send>) -> Self {
+    Self {
+      writer: Arc::new(Mutex::new(writer)),
+    }
+  }
+
+  /// Writes `event` as a single line, failing to write never fails the patch
+  pub(crate) fn emit(&self, event: &Event) {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_millis() as u64).unwrap_or_default();
+    let mut line = match serde_json::to_vec(&Line { timestamp, event }) {
+      Ok(line) => line,
+      Err(e) => return warn!("Could not serialize event {:?}: {}", event, e),
+    };
+    line.push(b'\n');
+    let result = match self.writer.lock() {
+      Ok(mut writer) => writer.write_all(&line).and_then(|_| writer.flush()),
+      Err(_) => return warn!("Event writer is poisoned"),
+    };
+    if let Err(e) = result {
+      warn!("Could not write event: {}", e);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_one_json_object_per_line() {
+        let output = Shared::default();
+        let writer = EventWriter::new(Box::new(output.clone()));
+        writer.emit(&Event::State { action: "Testing mirrors!".to_string() });
+        writer.emit(&Event::FileFailed { path: "UDKGame\\a.upk".to_string(), error: "HashMismatch".to_string() });
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "state");
+        assert_eq!(lines[0]["action"], "Testing mirrors!");
+        assert_eq!(lines[1]["event"], "file_failed");
+        assert!(lines[1]["timestamp"].is_u64());
+    }
+}
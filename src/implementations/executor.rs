@@ -0,0 +1,50 @@
+use std::future::Future;
+
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+use crate::structures::Executor;
+
+impl Executor {
+  pub(crate) fn new(handle: Handle) -> Self {
+    Self { handle: Some(handle) }
+  }
+
+  fn handle(&self) -> Handle {
+    self.handle.clone().unwrap_or_else(Handle::current)
+  }
+
+  /// Spawns `future`, `name` is recorded as the span the task runs in
+  pub(crate) fn spawn<F>(&self, name: &str, future: F) -> JoinHandle<F::Output>
+  where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+  {
+    self.handle().spawn(future.instrument(tracing::info_span!("task", name)))
+  }
+
+  /// Runs `function` on the blocking thread pool, `name` is recorded as the span it runs in
+  pub(crate) fn spawn_blocking<F, R>(&self, name: &str, function: F) -> JoinHandle<R>
+  where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+  {
+    let span = tracing::info_span!("blocking task", name);
+    self.handle().spawn_blocking(move || span.in_scope(function))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawns_on_the_given_runtime_from_outside_of_it() {
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build().unwrap();
+        let executor = Executor::new(runtime.handle().clone());
+        let inner = executor.clone();
+        let task = executor.spawn("test task", async move { inner.spawn_blocking("test blocking task", || 42).await.unwrap() });
+        assert_eq!(runtime.block_on(task).unwrap(), 42);
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use crate::functions::get_hash;
+use crate::structures::{Error, FileHashes};
+
+impl FileHashes {
+  /// The hash of the file at `path`, which is only read the first time it is asked for
+  pub(crate) fn get(&self, path: &Path) -> Result<String, Error> {
+    if let Some(hash) = self.hashes.lock()?.get(path) {
+      return Ok(hash.clone());
+    }
+    let hash = get_hash(path)?;
+    self.hashes.lock()?.insert(path.to_path_buf(), hash.clone());
+    Ok(hash)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+
+    #[test]
+    fn hashes_every_file_once() {
+        let temp_dir = TempDir::new("file_hashes");
+        let file = temp_dir.path().join("file.u");
+        std::fs::write(&file, b"first").unwrap();
+        let hashes = FileHashes::default();
+        let hash = hashes.get(&file).unwrap();
+        assert_eq!(hash, get_hash(&file).unwrap());
+
+        std::fs::write(&file, b"second").unwrap();
+        assert_eq!(hashes.clone().get(&file).unwrap(), hash);
+        assert_ne!(FileHashes::default().get(&file).unwrap(), hash);
+    }
+}
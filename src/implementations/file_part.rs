@@ -4,22 +4,22 @@ use download_async::http::StatusCode;
 use tracing::warn;
 use std::{fs::OpenOptions, io::{Write, Seek}};
 
-use crate::{structures::{FilePart, Mirrors}, Error, Progress};
+use crate::{structures::{Executor, FilePart, Mirrors}, Error, Progress};
 
 impl FilePart {
-  pub(crate) async fn download(self, mirrors: Mirrors, mirror_path: String, progress: Progress) -> Result<(Self, Vec<u8>), Error> {
-    tokio::task::Builder::new().name(&format!("Downloading chunk {} of {}", self.part_byte, self.file)).spawn(async move {
+  pub(crate) async fn download(self, mirrors: Mirrors, mirror_path: String, progress: Progress, executor: Executor) -> Result<(Self, Vec<u8>), Error> {
+    executor.spawn(&format!("Downloading chunk {} of {}", self.part_byte, self.file.display()), async move {
       let mirror = mirrors.get_mirror_async().await?;
       let mut downloader = download_async::Downloader::new();
       let uri = format!("{}/{}/{}", mirror.base, mirror.version, mirror_path).parse::<download_async::http::Uri>()?;
       warn!("Downloading FilePart: {}", uri);
       downloader.use_uri(uri);
       downloader.use_sockets(mirror.ip);
-      downloader.use_progress(progress);
+      downloader.use_progress(progress.clone());
       
-      let headers = downloader.headers().expect("Couldn't unwrap download_async headers option");
-      headers.append("User-Agent", format!("RenX-Patcher ({})", env!("CARGO_PKG_VERSION")).parse().unwrap());
-      headers.append("Range", format!("bytes={}-{}", &self.from, &self.to).parse().unwrap());
+      let headers = downloader.headers().ok_or_else(|| Error::None(format!("download_async returned no headers")))?;
+      headers.append("User-Agent", format!("RenX-Patcher ({})", env!("CARGO_PKG_VERSION")).parse().map_err(|_| Error::None(format!("Invalid User-Agent header")))?);
+      headers.append("Range", format!("bytes={}-{}", &self.from, &self.to).parse().map_err(|_| Error::None(format!("Invalid Range header")))?);
     
       let mut buffer = vec![];
       downloader.allow_http();
@@ -27,18 +27,19 @@ impl FilePart {
     
       let result = tokio::time::timeout(Duration::from_secs(60), response).await??;
       if result.status != StatusCode::PARTIAL_CONTENT {
-        return Err(Error::InvalidStatus(result.status.canonical_reason().unwrap().to_string()))
+        return Err(Error::InvalidStatus(result.status.to_string()))
       }
+      progress.add_mirror_bytes(&mirror.base, buffer.len() as u64);
       Ok((self, buffer))
-    })?.await?
+    }).await?
   }
 
-  pub(crate) async fn write_to_file(&self, buffer: Vec<u8>) -> Result<(), Error> {
+  pub(crate) async fn write_to_file(&self, buffer: Vec<u8>, executor: &Executor) -> Result<(), Error> {
     let from = self.from.clone();
     let file = self.file.clone();
     let part_byte = self.part_byte.clone();
 
-    tokio::task::Builder::new().name(&format!("write chunk {} to {}", part_byte, file)).spawn_blocking(move || {
+    executor.spawn_blocking(&format!("write chunk {} to {}", part_byte, file.display()), move || {
       let mut f = OpenOptions::new().read(true).write(true).create(true).open(&file)?;
       f.seek(SeekFrom::Start(from))?;
       f.write_all(&buffer)?;
@@ -46,6 +47,6 @@ impl FilePart {
       f.write(&[1])?;
       f.flush()?;
       Ok::<(), Error>(())
-    })?.await?
+    }).await?
   }
 }
\ No newline at end of file
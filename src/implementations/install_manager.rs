@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use tracing::info;
+
+use crate::functions::{append_extension, read_install_state};
+use crate::structures::{ChannelRelease, ContentStore, Error, Install, InstallManager, Mirrors, NamedUrl, PatchReport};
+use crate::PatcherBuilder;
+
+impl InstallManager {
+  /// Loads the installs persisted in `state_path`, starting without any if the file does not exist yet
+  pub fn load(state_path: impl AsRef<Path>, mirrors: Vec<NamedUrl>) -> Result<Self, Error> {
+    let state_path = state_path.as_ref().to_path_buf();
+    let installs = match std::fs::read_to_string(&state_path) {
+      Ok(text) => serde_json::from_str(&text)?,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+      Err(e) => return Err(e.into()),
+    };
+    Ok(Self {
+      state_path,
+      installs,
+      // Every install has its own version, the mirrors get it once they are used for an install
+      mirrors: Mirrors::new(mirrors, String::new()),
+      releases: HashMap::new(),
+      content_store: None,
+      configure: None,
+    })
+  }
+
+  /// Shares a content store between every install, so content that another install already has is not downloaded again
+  pub fn set_content_store(&mut self, location: impl AsRef<Path>, max_size: Option<u64>) -> &mut Self {
+    self.content_store = Some(ContentStore::new(location.as_ref().to_path_buf(), max_size));
+    self
+  }
+
+  /// Sets the version `channel` is on, installs following it are patched to this version
+  pub fn set_release(&mut self, channel: impl Into<String>, version: String, instructions_hash: String) -> &mut Self {
+    self.releases.insert(channel.into(), ChannelRelease { version, instructions_hash });
+    self
+  }
+
+  /// Called on the builder of every install before it is patched, e.g. to set callbacks or the validation mode
+  pub fn set_configure(&mut self, configure: impl Fn(&Install, &mut PatcherBuilder) + Send + Sync + 'static) -> &mut Self {
+    self.configure = Some(Box::new(configure));
+    self
+  }
+
+  pub fn installs(&self) -> &[Install] {
+    &self.installs
+  }
+
+  pub fn install(&self, name: &str) -> Option<&Install> {
+    self.installs.iter().find(|install| install.name == name)
+  }
+
+  /// Starts tracking an install on `channel`, fails if an install with the same name is tracked already
+  pub fn add_install(&mut self, name: impl Into<String>, channel: impl Into<String>, software_location: impl AsRef<Path>) -> Result<(), Error> {
+    let name = name.into();
+    if self.install(&name).is_some() {
+      return Err(Error::InvalidInput(format!("An install named {} already exists", name)));
+    }
+    self.installs.push(Install {
+      name,
+      channel: channel.into(),
+      software_location: software_location.as_ref().to_path_buf(),
+      version: None,
+    });
+    self.save()
+  }
+
+  /// Stops tracking an install, its files are left alone
+  pub fn remove_install(&mut self, name: &str) -> Result<Install, Error> {
+    let index = self.index_of(name)?;
+    let install = self.installs.remove(index);
+    self.save()?;
+    Ok(install)
+  }
+
+  /// Whether the install is not on the version of its channel, false if no release was set for the channel
+  ///
+  /// The state file in the install has to agree, so an install whose last patch was interrupted is patched again.
+  pub fn needs_patching(&self, name: &str) -> Result<bool, Error> {
+    let install = &self.installs[self.index_of(name)?];
+    let release = match self.releases.get(&install.channel) {
+      Some(release) => release,
+      None => return Ok(false),
+    };
+    if install.version.as_ref() != Some(&release.version) {
+      return Ok(true);
+    }
+    Ok(match read_install_state(&install.software_location)? {
+      Some(state) => state.version != release.version || !state.instructions_hash.eq_ignore_ascii_case(&release.instructions_hash),
+      None => true,
+    })
+  }
+
+  /// Patches the install to the version of its channel and records the version once it succeeded without skipping files
+  pub async fn patch(&mut self, name: &str) -> Result<PatchReport, Error> {
+    let index = self.index_of(name)?;
+    let install = self.installs[index].clone();
+    let release = self.releases.get(&install.channel).ok_or_else(|| Error::InvalidInput(format!("No release was set for channel {}", install.channel)))?.clone();
+
+    if !self.mirrors.tested {
+      // Keep the untested mirrors for the next attempt if none of them work
+      let mut mirrors = self.mirrors.clone();
+      mirrors.test_mirrors().await?;
+      if mirrors.is_empty() {
+        return Err(Error::NoMirrors());
+      }
+      self.mirrors = mirrors;
+    }
+
+    let mut builder = PatcherBuilder::new();
+    builder.set_software_location(&install.software_location);
+    builder.set_tested_mirrors(self.mirrors.clone(), release.version.clone(), release.instructions_hash);
+    if let Some(content_store) = &self.content_store {
+      builder.set_content_store(&content_store.location, content_store.max_size);
+    }
+    if let Some(configure) = &self.configure {
+      configure(&install, &mut builder);
+    }
+    info!("Patching install {} to version {} of {}", install.name, release.version, install.channel);
+    let report = builder.build()?.start_patching().await?.await?;
+
+    if report.skipped_files == 0 {
+      self.installs[index].version = Some(release.version);
+      self.save()?;
+    }
+    Ok(report)
+  }
+
+  /// Patches every install that is not on the version of its channel, one after the other
+  ///
+  /// Stops at the first install that fails, the versions of the installs patched before it are recorded.
+  pub async fn patch_all(&mut self) -> Result<BTreeMap<String, PatchReport>, Error> {
+    let mut reports = BTreeMap::new();
+    let names: Vec<String> = self.installs.iter().map(|install| install.name.clone()).collect();
+    for name in names {
+      if self.needs_patching(&name)? {
+        let report = self.patch(&name).await?;
+        reports.insert(name, report);
+      }
+    }
+    Ok(reports)
+  }
+
+  fn index_of(&self, name: &str) -> Result<usize, Error> {
+    self.installs.iter().position(|install| install.name == name).ok_or_else(|| Error::InvalidInput(format!("No install named {}", name)))
+  }
+
+  /// Writes the installs to the state file, replacing it at once so a crash never leaves a partial file behind
+  fn save(&self) -> Result<(), Error> {
+    if let Some(parent) = self.state_path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    let temporary = append_extension(&self.state_path, "tmp");
+    std::fs::write(&temporary, serde_json::to_string_pretty(&self.installs)?)?;
+    std::fs::rename(&temporary, &self.state_path)?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::TempDir;
+
+    #[test]
+    fn persists_installs_and_compares_them_with_their_channel() {
+        let temp_dir = TempDir::new("install_manager");
+        let directory = temp_dir.path();
+        let state_path = directory.join("installs.json");
+        let mut manager = InstallManager::load(&state_path, Vec::new()).unwrap();
+        manager.add_install("game", "release", directory.join("game")).unwrap();
+        manager.add_install("beta", "beta", directory.join("beta")).unwrap();
+        assert!(matches!(manager.add_install("game", "beta", directory.join("other")), Err(Error::InvalidInput(_))));
+
+        let mut manager = InstallManager::load(&state_path, Vec::new()).unwrap();
+        assert_eq!(manager.installs().len(), 2);
+        assert!(!manager.needs_patching("game").unwrap());
+        manager.set_release("release", "5.89".to_string(), "0".repeat(64));
+        assert!(manager.needs_patching("game").unwrap());
+        assert!(!manager.needs_patching("beta").unwrap());
+        assert!(matches!(manager.needs_patching("sdk"), Err(Error::InvalidInput(_))));
+
+        assert_eq!(manager.remove_install("beta").unwrap().channel, "beta");
+        assert_eq!(InstallManager::load(&state_path, Vec::new()).unwrap().installs().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn keeps_the_mirrors_untested_when_none_of_them_work() {
+        let temp_dir = TempDir::new("install_manager_mirrors");
+        let mirrors = vec![NamedUrl { name: "local".to_string(), url: "http://127.0.0.1:1/".to_string() }];
+        let mut manager = InstallManager::load(temp_dir.path().join("installs.json"), mirrors).unwrap();
+        manager.add_install("game", "release", temp_dir.path().join("game")).unwrap();
+        manager.set_release("release", "5.89".to_string(), "0".repeat(64));
+
+        assert!(matches!(manager.patch("game").await, Err(Error::NoMirrors())));
+        assert!(!manager.mirrors.tested);
+        assert_eq!(manager.mirrors.mirrors.len(), 1);
+    }
+}
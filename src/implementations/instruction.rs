@@ -1,26 +1,48 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::functions::{delete_file, get_hash, restore_backup};
-use crate::structures::{Action, DownloadEntry, Error, Instruction};
+use tracing::warn;
+
+use crate::functions::{backup_path, delete_file, ensure_inside_location, resolve_path_case, restore_backup};
+use crate::structures::{Action, ContentStore, DownloadEntry, Error, Executor, FileHashes, Instruction, Progress};
 
 impl Instruction {
-  pub async fn determine_action(self: Instruction, game_location: String) -> Result<Action, Error> {
-    let path = format!("{}{}", &game_location, &self.path);
-    let path_clone = path.clone();
-    let backup_path = format!("{}.bck", &path);
+  /// Where the file of this instruction is at `game_location`, matching the casing of existing files with `case_insensitive` set
+  pub(crate) fn location(&self, game_location: &Path, case_insensitive: bool) -> PathBuf {
+    if case_insensitive {
+      resolve_path_case(game_location, &self.path)
+    } else {
+      game_location.join(&self.path)
+    }
+  }
+
+  /// Compares the file on disk with the instruction and determines what has to happen to it
+  ///
+  /// With `case_insensitive` set, the path is matched against existing files and directories regardless of casing.
+  /// A `content_store` is consulted for the file itself and its patch files before anything is scheduled for download,
+  /// files that are already up to date are added to it.
+  /// With `dry_run` set, backups are not restored and outdated files are not removed.
+  /// Restored backups are counted in the report of `progress`. Files are hashed through `hashes`, on the blocking thread pool of `executor`.
+  pub(crate) async fn determine_action(self: Instruction, game_location: PathBuf, case_insensitive: bool, content_store: Option<ContentStore>, dry_run: bool, progress: Option<Progress>, hashes: FileHashes, executor: Executor) -> Result<Action, Error> {
+    let path_clone = game_location.join(&self.path);
     let mut backup_hash = None;
 
     let fut = move || {
-      let path_exists = std::fs::metadata(Path::new(&path)).is_ok();
-      let backup_exists = std::fs::metadata(Path::new(&backup_path)).is_ok();
+      let path = self.location(&game_location, case_insensitive);
+      let backup_path = backup_path(&path);
+      ensure_inside_location(&game_location, &path)?;
+      let path_exists = std::fs::metadata(&path).is_ok();
+      let backup_exists = std::fs::metadata(&backup_path).is_ok();
       // Determine wether we have to delete files, update them, or add them.
       if let Some(newest_hash) = self.newest_hash.clone() {
         let mut hash = None;
         // Update or download
         if path_exists {
-          hash = Some(get_hash(&path)?);
-          if newest_hash.eq(&hash.clone().unwrap()) {
+          hash = Some(hashes.get(&path)?);
+          if hash.as_ref() == Some(&newest_hash) {
             // File is already newest file
+            if let (Some(content_store), false) = (&content_store, dry_run) {
+              store_verified(content_store, &path, &newest_hash);
+            }
             if backup_exists {
               return Ok(Action::Delete(backup_path));
             }
@@ -29,21 +51,41 @@ impl Instruction {
         }
         
         if backup_exists {
-          backup_hash = Some(get_hash(&backup_path)?);
-          if backup_hash.clone().map(|backup_hash| newest_hash.eq(&backup_hash)).unwrap() {
+          backup_hash = Some(hashes.get(&backup_path)?);
+          if backup_hash.as_ref() == Some(&newest_hash) {
             // Restore backup file
-            restore_backup(&path)?;
+            if !dry_run {
+              restore_backup(&path)?;
+              if let Some(progress) = &progress {
+                progress.increment_restored_files();
+              }
+              if let Some(content_store) = &content_store {
+                store_verified(content_store, &path, &newest_hash);
+              }
+            }
             return Ok(Action::Nothing);
           }
         }
         
+        if let Some(content_store) = &content_store {
+          if content_store.fetch(&newest_hash, &path)? {
+            if backup_exists {
+              return Ok(Action::Delete(backup_path));
+            }
+            return Ok(Action::Nothing);
+          }
+        }
+
         // File is not up to date
         if let Some(previous_hash) = self.previous_hash.clone() {
           if self.has_delta {
             let delta_hash = self.delta_vcdiff_hash.clone().ok_or(Error::None(format!("Expected instruction to have delta_vcdiff_hash, however there was None: {:#?}", self)))?;
-            let download_path = format!("{}patcher/{}", &game_location, &delta_hash);
+            let download_path = game_location.join("patcher").join(&delta_hash);
 
-            if path_exists && previous_hash.eq(&hash.clone().unwrap()) {
+            if path_exists && hash.as_ref() == Some(&previous_hash) {
+              if let Some(content_store) = &content_store {
+                content_store.fetch(&delta_hash, &download_path)?;
+              }
               // Download delta
               return Ok(Action::Download(DownloadEntry {
                 mirror_path: format!("delta/{}_from_{}", &newest_hash, &previous_hash),
@@ -54,9 +96,17 @@ impl Instruction {
                 target_hash: newest_hash,
               }));
             // Check if there's a backup file, and restore it if it matches previous_hash
-            } else if backup_exists && previous_hash.eq(&backup_hash.clone().unwrap()) {
+            } else if backup_exists && backup_hash.as_ref() == Some(&previous_hash) {
               // Restore backup file
-              restore_backup(&path)?;
+              if !dry_run {
+                restore_backup(&path)?;
+                if let Some(progress) = &progress {
+                  progress.increment_restored_files();
+                }
+              }
+              if let Some(content_store) = &content_store {
+                content_store.fetch(&delta_hash, &download_path)?;
+              }
               return Ok(Action::Download(DownloadEntry {
                 mirror_path: format!("delta/{}_from_{}", &newest_hash, &previous_hash),
                 download_path: download_path,
@@ -69,15 +119,18 @@ impl Instruction {
           }
         }
 
-        if path_exists {
+        if path_exists && !dry_run {
           delete_file(path.clone())?;
         }
-        if backup_exists {
+        if backup_exists && !dry_run {
           delete_file(backup_path.clone())?;
         }
 
         let full_hash = self.full_vcdiff_hash.clone().ok_or(Error::None(format!("Expected instruction to have full_vcdiff_hash, however there was None: {:#?}", self)))?;
-        let download_path = format!("{}patcher/{}", &game_location, &full_hash);
+        let download_path = game_location.join("patcher").join(&full_hash);
+        if let Some(content_store) = &content_store {
+          content_store.fetch(&full_hash, &download_path)?;
+        }
         
         // Download full
         return Ok(Action::Download(DownloadEntry {
@@ -90,7 +143,7 @@ impl Instruction {
         }));
       } else {
         // Delete file
-        if backup_exists {
+        if backup_exists && !dry_run {
           delete_file(backup_path)?;
         }
         if path_exists {
@@ -100,6 +153,48 @@ impl Instruction {
       Ok::<Action, Error>(Action::Nothing)
     };
 
-    tokio::task::Builder::new().name(&format!("Determine action for {}", &path_clone)).spawn_blocking(fut)?.await?
+    executor.spawn_blocking(&format!("Determine action for {}", path_clone.display()), fut).await?
   }
 }
+
+/// Adds a file that was verified to be up to date to the content store, a store that can not be written to is only warned about
+fn store_verified(content_store: &ContentStore, path: &Path, hash: &str) {
+  if let Err(e) = content_store.insert(path, hash) {
+    warn!("Could not add {} to the content store: {}", path.display(), e);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::get_hash;
+    use crate::structures::TempDir;
+
+    #[tokio::test]
+    async fn stores_files_that_are_up_to_date() {
+        let temp_dir = TempDir::new("instruction_store");
+        let game_location = temp_dir.path().join("game");
+        std::fs::create_dir_all(&game_location).unwrap();
+        std::fs::write(game_location.join("file.u"), b"content").unwrap();
+        let newest_hash = get_hash(&game_location.join("file.u")).unwrap();
+        let instruction = Instruction {
+            path: "file.u".into(),
+            previous_hash: None,
+            newest_hash: Some(newest_hash.clone()),
+            full_vcdiff_hash: Some("0".repeat(64)),
+            delta_vcdiff_hash: None,
+            full_vcdiff_size: 7,
+            delta_vcdiff_size: 0,
+            has_delta: false,
+        };
+        let content_store = ContentStore::new(temp_dir.path().join("store"), None);
+
+        let action = instruction.clone().determine_action(game_location.clone(), false, Some(content_store.clone()), true, None, FileHashes::default(), Executor::default()).await.unwrap();
+        assert!(matches!(action, Action::Nothing));
+        assert!(std::fs::metadata(content_store.location.join(&newest_hash)).is_err());
+
+        let action = instruction.determine_action(game_location, false, Some(content_store.clone()), false, None, FileHashes::default(), Executor::default()).await.unwrap();
+        assert!(matches!(action, Action::Nothing));
+        assert_eq!(std::fs::read(content_store.location.join(&newest_hash)).unwrap(), b"content");
+    }
+}
@@ -0,0 +1,73 @@
+use std::convert::TryFrom;
+
+use crate::functions::normalize_manifest_path;
+use crate::structures::{Error, Instruction, Manifest, ManifestEntry, SUPPORTED_MANIFEST_VERSION};
+
+impl Manifest {
+  pub fn manifest_version(&self) -> u32 {
+    match self {
+      Self::Versioned { manifest_version, .. } => *manifest_version,
+      Self::Legacy(_) => 1,
+    }
+  }
+
+  /// Returns the raw entries, fails if the manifest is newer than this patcher understands
+  pub fn into_entries(self) -> Result<Vec<serde_json::Value>, Error> {
+    let manifest_version = self.manifest_version();
+    if manifest_version > SUPPORTED_MANIFEST_VERSION {
+      return Err(Error::UnsupportedManifestVersion(manifest_version));
+    }
+    match self {
+      Self::Versioned { instructions, .. } => Ok(instructions),
+      Self::Legacy(instructions) => Ok(instructions),
+    }
+  }
+}
+
+impl ManifestEntry {
+  /// Checks the hashes, sizes and path of this entry for consistency
+  pub fn validate(&self) -> Result<(), Error> {
+    let invalid = |reason: String| Err(Error::InvalidManifest(self.path.clone(), reason));
+
+    for (name, hash) in [("OldHash", &self.old_hash), ("NewHash", &self.new_hash), ("CompressedHash", &self.compressed_hash), ("DeltaHash", &self.delta_hash)] {
+      if let Some(hash) = hash {
+        if !is_sha256(hash) {
+          return invalid(format!("{} is not a SHA256 hash: {}", name, hash));
+        }
+      }
+    }
+    if self.has_delta && (self.delta_hash.is_none() || self.old_hash.is_none()) {
+      return invalid(format!("HasDelta is set, however DeltaHash or OldHash is missing"));
+    }
+    if !self.has_delta && self.delta_size != 0 {
+      return invalid(format!("HasDelta is not set, however DeltaSize is {}", self.delta_size));
+    }
+    if self.new_hash.is_some() && self.compressed_hash.is_none() {
+      return invalid(format!("NewHash is set, however CompressedHash is missing"));
+    }
+    normalize_manifest_path(&self.path)?;
+    Ok(())
+  }
+}
+
+impl TryFrom<ManifestEntry> for Instruction {
+  type Error = Error;
+
+  fn try_from(entry: ManifestEntry) -> Result<Self, Self::Error> {
+    entry.validate()?;
+    Ok(Instruction {
+      path:              normalize_manifest_path(&entry.path)?,
+      previous_hash:     entry.old_hash,
+      newest_hash:       entry.new_hash,
+      full_vcdiff_hash:  entry.compressed_hash,
+      delta_vcdiff_hash: entry.delta_hash,
+      full_vcdiff_size:  entry.full_replace_size,
+      delta_vcdiff_size: entry.delta_size,
+      has_delta:         entry.has_delta,
+    })
+  }
+}
+
+fn is_sha256(hash: &str) -> bool {
+  hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
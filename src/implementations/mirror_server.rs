@@ -0,0 +1,323 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader, AsyncBufReadExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::functions::normalize_manifest_path;
+use crate::structures::{Error, Executor, MirrorServer};
+
+/// The size of the file mirrors are speed tested with, see `Mirror::test_mirror`
+const TEST_FILE_SIZE: u64 = 10_000;
+/// Requests with a header larger than this are rejected
+const MAX_HEADER_SIZE: usize = 8 * 1024;
+
+impl MirrorServer {
+  /// Starts serving `root` on `address`, use port 0 to let the OS pick a free port
+  ///
+  /// `root` is expected to be in mirror layout, e.g. the `retained_location()` of a `Patcher`. A `10kb_file` is served even if `root` does not contain one.
+  pub async fn serve(root: impl AsRef<Path>, address: impl ToSocketAddrs) -> Result<Self, Error> {
+    let root = root.as_ref().to_path_buf();
+    let listener = TcpListener::bind(address).await?;
+    let local_addr = listener.local_addr()?;
+    let (shutdown, mut shutdown_receiver) = oneshot::channel();
+    info!("Serving {} as mirror on {}", root.display(), local_addr);
+
+    let served_root = root.clone();
+    // The server runs on the runtime it was started from
+    let executor = Executor::new(tokio::runtime::Handle::current());
+    let executor_connections = executor.clone();
+    let join_handle = executor.spawn("Mirror server", async move {
+      loop {
+        tokio::select! {
+          _ = &mut shutdown_receiver => break,
+          accepted = listener.accept() => {
+            let (stream, peer) = match accepted {
+              Ok(accepted) => accepted,
+              Err(e) => {
+                warn!("Mirror server failed to accept a connection: {}", e);
+                continue;
+              }
+            };
+            let root = served_root.clone();
+            executor_connections.spawn(&format!("Serving {}", peer), async move {
+              if let Err(e) = handle_connection(stream, &root).await {
+                warn!("Mirror server connection with {} failed: {}", peer, e);
+              }
+            });
+          }
+        }
+      }
+    });
+
+    Ok(Self {
+      root,
+      local_addr,
+      shutdown: Some(shutdown),
+      join_handle: Some(join_handle),
+    })
+  }
+
+  /// The address the server is listening on
+  pub fn local_addr(&self) -> SocketAddr {
+    self.local_addr
+  }
+
+  /// The directory being served
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  /// The URL to use as mirror, ending with a slash like the mirror URLs of the official mirror list
+  pub fn url(&self) -> String {
+    format!("http://{}/", self.local_addr)
+  }
+
+  /// Stops accepting connections and waits for the server to stop, connections in progress are finished
+  pub async fn shutdown(mut self) -> Result<(), Error> {
+    if let Some(shutdown) = self.shutdown.take() {
+      // The server might have stopped already, in which case there is nobody to notify
+      let _ = shutdown.send(());
+    }
+    if let Some(join_handle) = self.join_handle.take() {
+      join_handle.await?;
+    }
+    Ok(())
+  }
+}
+
+impl Drop for MirrorServer {
+  fn drop(&mut self) {
+    if let Some(shutdown) = self.shutdown.take() {
+      let _ = shutdown.send(());
+    }
+  }
+}
+
+struct Request {
+  method: String,
+  path: String,
+  range: Option<String>,
+  keep_alive: bool,
+}
+
+async fn handle_connection(stream: TcpStream, root: &Path) -> Result<(), Error> {
+  let (reader, mut writer) = stream.into_split();
+  let mut reader = BufReader::new(reader);
+  while let Some(request) = read_request(&mut reader).await? {
+    respond(&mut writer, root, &request).await?;
+    if !request.keep_alive {
+      break;
+    }
+  }
+  writer.shutdown().await?;
+  Ok(())
+}
+
+/// Reads the next request header, returns None once the client closed the connection
+async fn read_request<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<Option<Request>, Error> {
+  // Never buffer more than the header limit, even from a client that does not end its lines
+  let mut reader = (&mut *reader).take(MAX_HEADER_SIZE as u64 + 1);
+  let exceeded = || Error::InvalidInput(format!("Request header exceeds {} bytes", MAX_HEADER_SIZE));
+  let mut request_line = String::new();
+  if reader.read_line(&mut request_line).await? == 0 {
+    return Ok(None);
+  }
+  if reader.limit() == 0 {
+    return Err(exceeded());
+  }
+  let mut parts = request_line.split_whitespace();
+  let method = parts.next().unwrap_or_default().to_string();
+  let path = parts.next().unwrap_or_default().to_string();
+  let version = parts.next().unwrap_or_default().to_string();
+
+  let mut range = None;
+  let mut keep_alive = version == "HTTP/1.1";
+  loop {
+    let mut line = String::new();
+    let read = reader.read_line(&mut line).await?;
+    if reader.limit() == 0 {
+      return Err(exceeded());
+    }
+    let line = line.trim_end();
+    if read == 0 || line.is_empty() {
+      break;
+    }
+    if let Some((name, value)) = line.split_once(':') {
+      let value = value.trim();
+      if name.eq_ignore_ascii_case("range") {
+        range = Some(value.to_string());
+      } else if name.eq_ignore_ascii_case("connection") {
+        keep_alive = !value.eq_ignore_ascii_case("close");
+      }
+    }
+  }
+  Ok(Some(Request { method, path, range, keep_alive }))
+}
+
+/// Resolves the request path to a file inside of `root`, None if the path is unsafe
+fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+  let path = path.split(['?', '#']).next().unwrap_or_default().trim_start_matches('/');
+  normalize_manifest_path(path).ok().map(|relative| root.join(relative))
+}
+
+/// Parses a single `bytes=` range against a file of `length` bytes
+///
+/// Returns None if the range should be ignored and the whole file served, Some(Err(())) if it cannot be satisfied.
+fn parse_range(range: &str, length: u64) -> Option<Result<(u64, u64), ()>> {
+  let range = range.strip_prefix("bytes=")?;
+  // Multipart responses are not supported, clients asking for several ranges get the whole file
+  if range.contains(',') {
+    return None;
+  }
+  let (from, to) = range.split_once('-')?;
+  let (from, to) = match (from.trim(), to.trim()) {
+    ("", suffix) => {
+      let suffix = suffix.parse::<u64>().ok()?;
+      if suffix == 0 {
+        return Some(Err(()));
+      }
+      (length.saturating_sub(suffix), length.saturating_sub(1))
+    },
+    (from, "") => (from.parse::<u64>().ok()?, length.saturating_sub(1)),
+    (from, to) => (from.parse::<u64>().ok()?, to.parse::<u64>().ok()?.min(length.saturating_sub(1))),
+  };
+  if from > to || from >= length {
+    return Some(Err(()));
+  }
+  Some(Ok((from, to)))
+}
+
+async fn respond<W: AsyncWriteExt + Unpin>(writer: &mut W, root: &Path, request: &Request) -> Result<(), Error> {
+  let head_only = match request.method.as_str() {
+    "GET" => false,
+    "HEAD" => true,
+    _ => return write_status(writer, "405 Method Not Allowed", request.keep_alive).await,
+  };
+  let path = match resolve(root, &request.path) {
+    Some(path) => path,
+    None => return write_status(writer, "404 Not Found", request.keep_alive).await,
+  };
+
+  let file = match tokio::fs::File::open(&path).await {
+    Ok(file) if file.metadata().await?.is_file() => Some(file),
+    _ if request.path.trim_start_matches('/') == "10kb_file" => None,
+    _ => return write_status(writer, "404 Not Found", request.keep_alive).await,
+  };
+  let length = match &file {
+    Some(file) => file.metadata().await?.len(),
+    None => TEST_FILE_SIZE,
+  };
+
+  let (status, from, to) = match request.range.as_deref().and_then(|range| parse_range(range, length)) {
+    Some(Ok((from, to))) => ("206 Partial Content", from, to),
+    Some(Err(())) => {
+      let header = format!("HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\n{}\r\n", length, connection_header(request.keep_alive));
+      writer.write_all(header.as_bytes()).await?;
+      return Ok(writer.flush().await?);
+    },
+    None => ("200 OK", 0, length.saturating_sub(1)),
+  };
+  let content_length = if length == 0 { 0 } else { to - from + 1 };
+
+  let mut header = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nContent-Type: application/octet-stream\r\nAccept-Ranges: bytes\r\n", status, content_length);
+  if request.range.is_some() && status.starts_with("206") {
+    header.push_str(&format!("Content-Range: bytes {}-{}/{}\r\n", from, to, length));
+  }
+  header.push_str(&connection_header(request.keep_alive));
+  header.push_str("\r\n");
+  writer.write_all(header.as_bytes()).await?;
+
+  if !head_only {
+    match file {
+      Some(mut file) => {
+        file.seek(std::io::SeekFrom::Start(from)).await?;
+        tokio::io::copy(&mut file.take(content_length), writer).await?;
+      },
+      None => writer.write_all(&vec![0u8; content_length as usize]).await?,
+    }
+  }
+  Ok(writer.flush().await?)
+}
+
+fn connection_header(keep_alive: bool) -> String {
+  format!("Connection: {}\r\n", if keep_alive { "keep-alive" } else { "close" })
+}
+
+async fn write_status<W: AsyncWriteExt + Unpin>(writer: &mut W, status: &str, keep_alive: bool) -> Result<(), Error> {
+  let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n{}\r\n", status, connection_header(keep_alive));
+  writer.write_all(response.as_bytes()).await?;
+  Ok(writer.flush().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structures::{FilePart, Mirrors, NamedUrl, Progress, TempDir};
+
+    async fn get(server: &MirrorServer, path: &str, range: Option<&str>) -> String {
+        let mut stream = TcpStream::connect(server.local_addr()).await.unwrap();
+        let range = range.map(|range| format!("Range: {}\r\n", range)).unwrap_or_default();
+        stream.write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n", path, range).as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    }
+
+    #[tokio::test]
+    async fn serves_patch_files_with_ranges_on_localhost() {
+        let temp_dir = TempDir::new("mirror_server");
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("1.0").join("full")).unwrap();
+        std::fs::write(root.join("1.0").join("full").join("ABCD"), b"0123456789").unwrap();
+        let server = MirrorServer::serve(&root, "127.0.0.1:0").await.unwrap();
+
+        let response = get(&server, "/1.0/full/ABCD", Some("bytes=2-5")).await;
+        assert!(response.starts_with("HTTP/1.1 206"), "{}", response);
+        assert!(response.contains("Content-Range: bytes 2-5/10\r\n"));
+        assert!(response.ends_with("\r\n\r\n2345"));
+
+        assert!(get(&server, "/1.0/full/ABCD", None).await.ends_with("\r\n\r\n0123456789"));
+        assert!(get(&server, "/10kb_file", None).await.contains("Content-Length: 10000\r\n"));
+        assert!(get(&server, "/1.0/full/ABCD", Some("bytes=10-")).await.starts_with("HTTP/1.1 416"));
+        assert!(get(&server, "/../1.0/full/ABCD", None).await.starts_with("HTTP/1.1 404"));
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_headers_without_line_endings() {
+        let temp_dir = TempDir::new("mirror_server_header");
+        let server = MirrorServer::serve(temp_dir.path(), "127.0.0.1:0").await.unwrap();
+        let mut stream = TcpStream::connect(server.local_addr()).await.unwrap();
+        stream.write_all(format!("GET /{}", "a".repeat(MAX_HEADER_SIZE * 2)).as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        // The server closes the connection without answering
+        let _ = stream.read_to_end(&mut response).await;
+        assert!(response.is_empty());
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn serves_the_patchers_own_client() {
+        let temp_dir = TempDir::new("mirror_server_client");
+        let root = temp_dir.path().join("mirror");
+        std::fs::create_dir_all(root.join("1.0").join("full")).unwrap();
+        std::fs::create_dir_all(root.join("1.0").join("delta")).unwrap();
+        std::fs::write(root.join("1.0").join("full").join("ABCD"), b"0123456789").unwrap();
+        std::fs::write(root.join("1.0").join("delta").join("ABCD_EFGH"), b"abcdefghij").unwrap();
+        let server = MirrorServer::serve(&root, "127.0.0.1:0").await.unwrap();
+        let mirrors = Mirrors::new(vec![NamedUrl { name: "local".to_string(), url: format!("http://{}/", server.local_addr()) }], "1.0".to_string());
+
+        mirrors.mirrors[0].clone().test_mirror().await.unwrap();
+        let part = FilePart::new(temp_dir.path().join("patch"), 0, 2, 5);
+        let (_, full) = part.clone().download(mirrors.clone(), "full/ABCD".to_string(), Progress::new(), Executor::default()).await.unwrap();
+        assert_eq!(full, b"2345");
+        let (_, delta) = part.download(mirrors, "delta/ABCD_EFGH".to_string(), Progress::new(), Executor::default()).await.unwrap();
+        assert_eq!(delta, b"cdef");
+
+        server.shutdown().await.unwrap();
+    }
+}
@@ -24,10 +24,20 @@ impl Mirrors {
       }
     }
     Self {
-      mirrors
+      mirrors,
+      tested: false,
     }
   }
 
+    /// The same mirrors, keeping their test results and error counts, for downloading `version`
+    pub(crate) fn with_version(&self, version: &str) -> Self {
+      let version = Arc::new(version.to_string());
+      Self {
+        mirrors: self.mirrors.iter().map(|mirror| Mirror { version: version.clone(), ..mirror.clone() }).collect(),
+        tested: self.tested,
+      }
+    }
+
     pub fn is_empty(&self) -> bool {
       self.mirrors.is_empty()
     }
@@ -52,6 +62,9 @@ impl Mirrors {
     }
   
     pub fn get_mirror(&self) -> Result<Mirror, Error> {
+      if !self.mirrors.iter().any(|mirror| mirror.enabled.load(Ordering::Relaxed)) {
+        return Err(Error::NoMirrors());
+      }
       for i in 0.. {
         for mirror in self.mirrors.iter() {
           if mirror.enabled.load(Ordering::Relaxed) && Arc::strong_count(&mirror.base) == i {
@@ -81,7 +94,7 @@ impl Mirrors {
         }
       }
       if self.mirrors.len() > 1 {
-        self.mirrors.sort_by(|a,b| b.speed.partial_cmp(&a.speed).expect("mirrors.rs: Couldn't compare a.speed with b.speed."));
+        self.mirrors.sort_by(|a,b| b.speed.total_cmp(&a.speed));
         let best_speed = self.mirrors[0].speed;
         for elem in self.mirrors.iter() {
           if elem.speed < best_speed / 4.0 {
@@ -89,6 +102,23 @@ impl Mirrors {
           }
         }
       }
+      self.tested = true;
       Ok(())
     }
-  }
\ No newline at end of file
+  }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_version_keeps_the_test_results() {
+        let mut mirrors = Mirrors::new(vec![NamedUrl { name: "local".to_string(), url: "http://127.0.0.1:1/".to_string() }], String::new());
+        mirrors.tested = true;
+        let versioned = mirrors.with_version("5.89");
+        assert!(versioned.tested);
+        assert_eq!(versioned.mirrors[0].version.as_str(), "5.89");
+        mirrors.disable(0);
+        assert!(!versioned.mirrors[0].enabled.load(Ordering::Relaxed));
+    }
+}
@@ -2,8 +2,22 @@ pub(crate) mod mirror;
 pub(crate) mod mirrors;
 pub(crate) mod response;
 pub mod error;
-pub(crate) mod directory;
+pub(crate) mod path_index;
 pub(crate) mod buffered_writer;
 pub(crate) mod instruction;
 pub mod progress;
-pub(crate) mod file_part;
\ No newline at end of file
+pub(crate) mod file_part;
+pub(crate) mod manifest;
+pub mod content_store;
+#[cfg(feature = "mirror-server")]
+pub(crate) mod mirror_server;
+pub mod disk_space;
+pub(crate) mod disk_budget;
+pub(crate) mod file_hashes;
+pub(crate) mod event_writer;
+pub(crate) mod patch_handle;
+pub(crate) mod executor;
+pub(crate) mod run_guard;
+pub mod install_manager;
+#[cfg(test)]
+pub(crate) mod temp_dir;
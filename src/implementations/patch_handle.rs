@@ -0,0 +1,43 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::sync::oneshot;
+
+use crate::structures::{Error, PatchHandle, PatchReport};
+
+impl PatchHandle {
+  /// Creates a handle together with the sender the run reports its outcome to
+  pub(crate) fn new() -> (oneshot::Sender<Result<PatchReport, Error>>, Self) {
+    let (sender, receiver) = oneshot::channel();
+    (sender, Self { receiver })
+  }
+}
+
+impl Future for PatchHandle {
+  type Output = Result<PatchReport, Error>;
+
+  fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    Pin::new(&mut self.receiver).poll(cx).map(|outcome| match outcome {
+      Ok(outcome) => outcome,
+      // The run panicked or was aborted before it could report anything
+      Err(_) => Err(Error::InvalidState("The patcher stopped without a result")),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resolves_to_the_reported_outcome() {
+        let (sender, handle) = PatchHandle::new();
+        sender.send(Ok(PatchReport { patched_files: 3, ..Default::default() })).unwrap();
+        assert_eq!(handle.await.unwrap().patched_files, 3);
+
+        let (sender, handle) = PatchHandle::new();
+        drop(sender);
+        assert!(matches!(handle.await, Err(Error::InvalidState(_))));
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::structures::PathIndex;
+
+impl PathIndex {
+    pub fn new(case_insensitive: bool) -> Self {
+      Self {
+        case_insensitive,
+        directories: HashSet::new(),
+        files: HashSet::new(),
+      }
+    }
+
+    /// Adds a file, and every directory leading up to it, to the index
+    pub fn insert_file(&mut self, file: &Path) {
+      let key = self.key(file);
+      let mut directory = key.parent();
+      while let Some(dir) = directory {
+        if dir.as_os_str().is_empty() || !self.directories.insert(dir.to_path_buf()) {
+          break;
+        }
+        directory = dir.parent();
+      }
+      self.files.insert(key);
+    }
+
+    /// Adds a directory, and every directory leading up to it, to the index
+    pub fn insert_directory(&mut self, directory: &Path) {
+      let key = self.key(directory);
+      let mut directory = Some(key.as_path());
+      while let Some(dir) = directory {
+        if dir.as_os_str().is_empty() || !self.directories.insert(dir.to_path_buf()) {
+          break;
+        }
+        directory = dir.parent();
+      }
+    }
+
+    pub fn directory_exists(&self, path: &Path) -> bool {
+      self.directories.contains(&self.key(path))
+    }
+
+    pub fn file_exists(&self, file: &Path) -> bool {
+      self.files.contains(&self.key(file))
+    }
+
+    /// The path as it is stored in the index
+    fn key(&self, path: &Path) -> PathBuf {
+      if self.case_insensitive {
+        path.iter().map(|component| component.to_string_lossy().to_lowercase()).collect()
+      } else {
+        path.to_path_buf()
+      }
+    }
+  }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_insensitive_lookups() {
+        let mut index = PathIndex::new(true);
+        index.insert_file(Path::new("Binaries/Win64/UDK.exe"));
+        assert!(index.file_exists(Path::new("binaries/WIN64/udk.EXE")));
+        assert!(index.directory_exists(Path::new("BINARIES")));
+        assert!(!index.file_exists(Path::new("Binaries/Win64")));
+
+        let mut index = PathIndex::new(false);
+        index.insert_file(Path::new("Binaries/Win64/UDK.exe"));
+        assert!(!index.file_exists(Path::new("binaries/Win64/UDK.exe")));
+        assert!(index.directory_exists(Path::new("Binaries/Win64")));
+    }
+}
@@ -1,9 +1,10 @@
 use async_trait::async_trait;
 use tracing::info;
 
-use crate::structures::{Error, Progress};
+use crate::structures::{Error, Event, EventWriter, PatchReport, Progress, ProgressSnapshot};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 #[async_trait]
 impl download_async::Progress for Progress {
@@ -30,6 +31,36 @@ impl Progress {
             downloaded_bytes: Arc::new((AtomicU64::new(0), AtomicU64::new(0))),
             patched_files: Arc::new((AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0))),
             patched_bytes: Arc::new((AtomicU64::new(0), AtomicU64::new(0))),
+            events: None,
+            report: Arc::new(Mutex::new(PatchReport::default())),
+            action_started: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Creates a progress that also writes its changes, and the events passed to `emit`, to `events`
+    pub(crate) fn with_events(events: Option<EventWriter>) -> Self {
+        Self {
+            events,
+            ..Self::new()
+        }
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        if let Some(events) = &self.events {
+            events.emit(&event);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            processed_instructions: self.processed_instructions.0.load(Ordering::Relaxed),
+            total_instructions: self.processed_instructions.1.load(Ordering::Relaxed),
+            downloaded_files: self.downloaded_files.0.load(Ordering::Relaxed),
+            total_download_files: self.downloaded_files.1.load(Ordering::Relaxed),
+            downloaded_bytes: self.downloaded_bytes.0.load(Ordering::Relaxed),
+            total_download_bytes: self.downloaded_bytes.1.load(Ordering::Relaxed),
+            patched_files: self.patched_files.0.load(Ordering::Relaxed),
+            total_patch_files: self.patched_files.2.load(Ordering::Relaxed),
         }
     }
 
@@ -39,7 +70,12 @@ impl Progress {
 
     pub(crate) fn set_current_action(&self, value: String) -> Result<(), Error> {
         info!("Current action: {}", value);
-        *self.current_action.lock()? = value;
+        let previous = std::mem::replace(&mut *self.current_action.lock()?, value.clone());
+        let started = std::mem::replace(&mut *self.action_started.lock()?, Instant::now());
+        if !previous.is_empty() {
+            self.report.lock()?.phases.push((previous, started.elapsed()));
+        }
+        self.emit(Event::State { action: value });
         Ok(())
     }
 
@@ -72,4 +108,82 @@ impl Progress {
     pub(crate) fn increment_completed_patches(&self) {
         self.patched_files.0.fetch_add(1, Ordering::Relaxed);
     }
-}
\ No newline at end of file
+
+    pub(crate) fn add_mirror_bytes(&self, mirror: &str, bytes: u64) {
+        if let Ok(mut report) = self.report.lock() {
+            *report.mirror_bytes.entry(mirror.to_string()).or_default() += bytes;
+        }
+    }
+
+    pub(crate) fn increment_downloaded_patch_files(&self, delta: bool) {
+        if let Ok(mut report) = self.report.lock() {
+            if delta {
+                report.delta_downloads += 1;
+            } else {
+                report.full_downloads += 1;
+            }
+        }
+    }
+
+    pub(crate) fn increment_deleted_files(&self) {
+        if let Ok(mut report) = self.report.lock() {
+            report.deleted_files += 1;
+        }
+    }
+
+    pub(crate) fn increment_restored_files(&self) {
+        if let Ok(mut report) = self.report.lock() {
+            report.restored_files += 1;
+        }
+    }
+
+    /// Records a file that will not be up to date at the end of the run, together with the reason as a warning
+    pub(crate) fn add_skipped_file(&self, warning: String) {
+        if let Ok(mut report) = self.report.lock() {
+            report.skipped_files += 1;
+            report.warnings.push(warning);
+        }
+    }
+
+    pub(crate) fn skipped_files(&self) -> u64 {
+        self.report.lock().map(|report| report.skipped_files).unwrap_or(0)
+    }
+
+    /// Ends the current phase and returns the report with the final counts
+    pub(crate) fn finish_report(&self) -> Result<PatchReport, Error> {
+        let current_action = self.current_action.lock()?.clone();
+        let mut report = self.report.lock()?;
+        if !current_action.is_empty() {
+            report.phases.push((current_action, self.action_started.lock()?.elapsed()));
+        }
+        report.verified_files = self.processed_instructions.0.load(Ordering::Relaxed);
+        report.patched_files = self.patched_files.0.load(Ordering::Relaxed);
+        Ok(report.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_times_each_action_and_collects_counts() {
+        let progress = Progress::new();
+        progress.set_current_action("Verifying".to_string()).unwrap();
+        progress.increment_processed_instructions();
+        progress.set_current_action("Patching".to_string()).unwrap();
+        progress.increment_downloaded_patch_files(true);
+        progress.add_mirror_bytes("http://mirror/", 10);
+        progress.add_mirror_bytes("http://mirror/", 5);
+        progress.increment_completed_patches();
+        progress.add_skipped_file("Skipped a file".to_string());
+
+        let report = progress.finish_report().unwrap();
+        let phases: Vec<&str> = report.phases.iter().map(|(phase, _)| phase.as_str()).collect();
+        assert_eq!(phases, vec!["Verifying", "Patching"]);
+        assert_eq!((report.verified_files, report.delta_downloads, report.full_downloads, report.patched_files), (1, 1, 0, 1));
+        assert_eq!(report.mirror_bytes.get("http://mirror/"), Some(&15));
+        assert_eq!(report.skipped_files, 1);
+        assert_eq!(report.warnings, vec!["Skipped a file".to_string()]);
+    }
+}
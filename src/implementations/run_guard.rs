@@ -0,0 +1,9 @@
+use std::sync::atomic::Ordering;
+
+use crate::structures::RunGuard;
+
+impl Drop for RunGuard {
+  fn drop(&mut self) {
+    self.in_progress.store(false, Ordering::SeqCst);
+  }
+}
@@ -0,0 +1,28 @@
+use crate::structures::TempDir;
+
+use std::path::Path;
+
+impl TempDir {
+  /// Creates an empty directory named after `name` and the process id, replacing what a previous run left behind
+  pub(crate) fn new(name: &str) -> Self {
+    Self::new_in(&std::env::temp_dir(), name)
+  }
+
+  /// Like `new`, inside of `parent` instead of the system temp directory
+  pub(crate) fn new_in(parent: &Path, name: &str) -> Self {
+    let path = parent.join(format!("renx_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).expect("Failed to create a temporary directory");
+    Self { path }
+  }
+
+  pub(crate) fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.path);
+  }
+}
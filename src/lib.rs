@@ -3,14 +3,36 @@ extern crate tokio;
 mod structures;
 mod functions;
 mod implementations;
-mod traits;
 mod patcher;
 mod patcher_builder;
 mod pausable;
+pub mod blocking;
+#[cfg(feature = "c-api")]
+pub mod c_api;
 
 pub use patcher::Patcher as Patcher;
 pub use patcher_builder::PatcherBuilder as PatcherBuilder;
 pub use structures::Error as Error;
 pub use structures::NamedUrl as NamedUrl;
 pub use structures::Progress as Progress;
+pub use structures::ValidationMode as ValidationMode;
+pub use structures::UnversionedMode as UnversionedMode;
+pub use structures::QuarantinedFile as QuarantinedFile;
+pub use structures::DeduplicationMode as DeduplicationMode;
+pub use structures::ContentStore as ContentStore;
+pub use structures::PatchFileRetention as PatchFileRetention;
+pub use structures::DiskSpace as DiskSpace;
+pub use structures::Plan as Plan;
+pub use structures::PlannedUpdate as PlannedUpdate;
+pub use structures::PatchReport as PatchReport;
+pub use structures::PatchHandle as PatchHandle;
+pub use structures::Install as Install;
+pub use structures::ChannelRelease as ChannelRelease;
+pub use structures::InstallManager as InstallManager;
+pub use structures::InstallState as InstallState;
+#[cfg(feature = "mirror-server")]
+pub use structures::MirrorServer as MirrorServer;
 pub use functions::human_readable_bytesize as human_readable_bytesize;
+pub use functions::DEFAULT_UNVERSIONED_ALLOWLIST as DEFAULT_UNVERSIONED_ALLOWLIST;
+pub use functions::QUARANTINE_DIRECTORY as QUARANTINE_DIRECTORY;
+pub use functions::INSTALL_STATE_FILE as INSTALL_STATE_FILE;
@@ -1,84 +1,254 @@
 //Standard library
-use std::sync::{Arc};
-use std::sync::atomic::AtomicBool;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-use crate::functions::{flow, remove_unversioned, download_instructions};
+use tokio::sync::oneshot;
+
+use crate::functions::{disk_space_requirement, export_retained, flow, find_unversioned, invalidate_install_state, list_quarantined, plan_actions, purge_quarantine, read_install_state, remove_unversioned, restore_quarantined, download_instructions, write_install_state};
 use crate::pausable::{BackgroundService, FutureContext};
 use crate::pausable::PausableTrait;
-use crate::structures::{Error, Mirrors, Progress};
+use crate::structures::{Action, ContentStore, DeduplicationMode, DiskSpace, Error, Event, EventWriter, Executor, FileHashes, InstallState, Mirrors, PatchFileRetention, PatchHandle, PatchReport, Plan, PlannedUpdate, Progress, QuarantinedFile, RunGuard, Subscribers, UnversionedMode, ValidationMode};
 
 pub struct Patcher {
   pub in_progress: Arc<AtomicBool>,
   pub(crate) join_handle: Option<tokio::task::JoinHandle<()>>,
-  pub(crate) software_location: String,
+  pub(crate) software_location: PathBuf,
   pub(crate) mirrors: Mirrors,
+  pub(crate) version: String,
   pub(crate) instructions_hash: String,
-  pub(crate) success_callback: Option<Box<dyn FnOnce() + Send>>,
-  pub(crate) failure_callback: Option<Box<dyn FnOnce(Error) + Send>>,
-  pub(crate) progress_callback: Option<Box<dyn Fn(&Progress) + Send>>,
+  pub(crate) validation_mode: ValidationMode,
+  pub(crate) unversioned_allowlist: Vec<glob::Pattern>,
+  pub(crate) unversioned_mode: UnversionedMode,
+  pub(crate) quarantine_max_age: Option<Duration>,
+  pub(crate) case_insensitive_paths: bool,
+  pub(crate) deduplication_mode: DeduplicationMode,
+  pub(crate) content_store: Option<ContentStore>,
+  pub(crate) patch_file_retention: PatchFileRetention,
+  pub(crate) disk_space_budget: Option<u64>,
+  pub(crate) event_writer: Option<EventWriter>,
+  pub(crate) subscribers: Arc<Mutex<Subscribers>>,
+  pub(crate) executor: Executor,
+  /// The context of the current or last run, replaced once a cancelled run is followed by another
   pub(crate) context: Arc<FutureContext>
 }
 
 impl Patcher {
-  pub async fn factory_reset(&mut self) {
+  /// Patches and then removes every unversioned file, the returned handle resolves once it is done
+  ///
+  /// Fails with `AlreadyStarted` while another run is in progress.
+  pub async fn factory_reset(&mut self) -> Result<PatchHandle, Error> {
+    self.start_run("Factory reset", true)
+  }
+
+  /// Starts patching in the background, the returned handle resolves to the report once it is done
+  ///
+  /// Files that are missing or broken are redownloaded as well, so this also repairs the installation.
+  /// The success and failure callbacks are called before the handle resolves. Fails with `AlreadyStarted` while another run is in progress,
+  /// once it finished the patcher can be started again.
+  pub async fn start_patching(&mut self) -> Result<PatchHandle, Error> {
+    self.start_run("Patching", false)
+  }
+
+  /// Spawns a run that patches the install, with `factory_reset` set it then removes every unversioned file as well
+  fn start_run(&mut self, name: &str, factory_reset: bool) -> Result<PatchHandle, Error> {
     let mirrors = self.mirrors.clone();
     let software_location = self.software_location.clone();
+    let version = self.version.clone();
     let instructions_hash = self.instructions_hash.clone();
-    let success_callback = self.success_callback.take().expect("Can only start patching once");
-    let failure_callback = self.failure_callback.take().expect("Can only start patching once");
-    let progress_callback = self.progress_callback.take().expect("Can only start patching once");
+    let validation_mode = self.validation_mode;
+    let case_insensitive_paths = self.case_insensitive_paths;
+    let deduplication_mode = self.deduplication_mode;
+    let content_store = self.content_store.clone();
+    let retain_in = self.retained_location().map(|location| location.join(&self.version));
+    let disk_space_budget = self.disk_space_budget;
+    let event_writer = self.event_writer.clone();
+    let executor = self.executor.clone();
+    let unversioned_allowlist = self.unversioned_allowlist.clone();
+    let unversioned_mode = self.unversioned_mode;
+    let quarantine_max_age = self.quarantine_max_age;
+    let (progress_callback, run_guard) = self.begin_run()?;
+    let subscribers = self.subscribers.clone();
     let context = self.context.clone();
+    let (sender, handle) = PatchHandle::new();
 
-    self.join_handle = Some(tokio::task::spawn(async move {
-
+    self.join_handle = Some(self.executor.spawn(name, async move {
+      let progress = Progress::with_events(event_writer);
       let result = async {
-        let progress = Progress::new();
-
-        let (instructions, progress_callback) = download_instructions(mirrors.clone(), &instructions_hash, progress.clone(), progress_callback, context.clone()).pausable(context.clone()).await?;
-        let progress_callback = flow(mirrors.clone(), &software_location, instructions.clone(), progress.clone(), progress_callback, context.clone()).pausable(context.clone()).await?;
-        remove_unversioned(software_location, instructions, progress.clone(), progress_callback).pausable(context).await
+        let (instructions, progress_callback) = download_instructions(mirrors.clone(), &instructions_hash, progress.clone(), progress_callback, context.clone(), validation_mode, retain_in.clone()).pausable(context.clone()).await?;
+        invalidate_install_state(&software_location)?;
+        let progress_callback = flow(mirrors.clone(), &software_location, instructions.clone(), progress.clone(), progress_callback, context.clone(), validation_mode, case_insensitive_paths, deduplication_mode, content_store, retain_in, disk_space_budget, executor).pausable(context.clone()).await?;
+        if factory_reset {
+          remove_unversioned(&software_location, instructions.clone(), &unversioned_allowlist, unversioned_mode, case_insensitive_paths, progress.clone(), progress_callback).pausable(context).await?;
+          if let Some(max_age) = quarantine_max_age {
+            purge_quarantine(&software_location, max_age)?;
+          }
+        }
+        // A lenient run that skipped files leaves the install incomplete, so it is not recorded as up to date
+        if progress.skipped_files() == 0 {
+          write_install_state(&software_location, &version, &instructions_hash, &instructions)?;
+        }
+        Ok::<(), Error>(())
       }.await;
-      if result.is_ok() {
-        tracing::info!("Calling success_callback");
-        success_callback();
-      } else if let Err(e) = result {
-        tracing::info!("Calling failure_callback");
-        failure_callback(e);
-      }
+      complete(&progress, result, &subscribers, run_guard, sender);
     }));
+    Ok(handle)
   }
 
-  pub async fn start_patching(&mut self) {
+  /// What the last patch that left every file up to date recorded in the install's state file, None if there is none
+  ///
+  /// The state file is removed as soon as a patch starts changing files, so an interrupted patch leaves no state behind.
+  pub fn install_state(&self) -> Result<Option<InstallState>, Error> {
+    read_install_state(&self.software_location)
+  }
+
+  /// The version the install was last successfully patched to, without verifying any files
+  pub fn installed_version(&self) -> Result<Option<String>, Error> {
+    Ok(self.install_state()?.map(|state| state.version))
+  }
+
+  /// Whether the install is not known to be on the version and instructions of this patcher, according to its state file
+  pub fn needs_patching(&self) -> Result<bool, Error> {
+    Ok(match self.install_state()? {
+      Some(state) => state.version != self.version || !state.instructions_hash.eq_ignore_ascii_case(&self.instructions_hash),
+      None => true,
+    })
+  }
+
+  /// Downloads the instructions and lists the files and directories a `factory_reset` would remove, without removing anything
+  pub async fn preview_unversioned(&self) -> Result<Vec<PathBuf>, Error> {
     let mirrors = self.mirrors.clone();
     let software_location = self.software_location.clone();
     let instructions_hash = self.instructions_hash.clone();
-    let success_callback = self.success_callback.take().expect("Can only start patching once");
-    let failure_callback = self.failure_callback.take().expect("Can only start patching once");
-    let progress_callback = self.progress_callback.take().expect("Can only start patching once");
+    let validation_mode = self.validation_mode;
+    let unversioned_allowlist = self.unversioned_allowlist.clone();
+    let case_insensitive_paths = self.case_insensitive_paths;
     let context = self.context.clone();
+    self.executor.spawn("Previewing unversioned files", async move {
+      let (instructions, _) = download_instructions(mirrors, &instructions_hash, Progress::new(), Box::new(|_| {}), context.clone(), validation_mode, None).pausable(context).await?;
+      find_unversioned(&software_location, &instructions, &unversioned_allowlist, case_insensitive_paths)
+    }).await?
+  }
 
-    self.join_handle = Some(tokio::task::spawn(async move {
-      let result = async {
-        let progress = Progress::new();
+  /// Downloads the instructions and estimates the disk space `start_patching` needs, without changing any files
+  pub async fn disk_space_requirement(&self) -> Result<DiskSpace, Error> {
+    Ok(self.plan().await?.disk_space)
+  }
 
-        let (instructions, progress_callback) = download_instructions(mirrors.clone(), &instructions_hash, progress.clone(), progress_callback, context.clone()).pausable(context.clone()).await?;
-        flow(mirrors.clone(), &software_location, instructions.clone(), progress.clone(), progress_callback, context.clone()).pausable(context.clone()).await
-      }.await;
-      if result.is_ok() {
-        tracing::info!("Calling success_callback");
-        success_callback();
-      } else if let Err(e) = result {
-        tracing::info!("Calling failure_callback");
-        failure_callback(e);
+  /// Downloads the instructions and determines what `start_patching` would change, without changing any files
+  ///
+  /// Every file is hashed, so this takes about as long as the verification phase of patching.
+  pub async fn plan(&self) -> Result<Plan, Error> {
+    let mirrors = self.mirrors.clone();
+    let software_location = self.software_location.clone();
+    let instructions_hash = self.instructions_hash.clone();
+    let validation_mode = self.validation_mode;
+    let case_insensitive_paths = self.case_insensitive_paths;
+    let disk_space_budget = self.disk_space_budget;
+    let context = self.context.clone();
+    let executor = self.executor.clone();
+    self.executor.spawn("Planning", async move {
+      let (instructions, _) = download_instructions(mirrors, &instructions_hash, Progress::new(), Box::new(|_| {}), context.clone(), validation_mode, None).pausable(context).await?;
+      let mut downloads = Vec::new();
+      let mut deletions = Vec::new();
+      let mut failures = Vec::new();
+      for action in plan_actions(&software_location, &instructions, case_insensitive_paths, None, &FileHashes::default(), &executor).await {
+        match action {
+          Ok(Action::Download(download_entry)) => downloads.push(download_entry),
+          Ok(Action::Delete(file)) => deletions.push(file.strip_prefix(&software_location).map(Path::to_path_buf).unwrap_or(file)),
+          Ok(Action::Nothing) => {},
+          Err((path, e)) => failures.push((path.display().to_string(), e)),
+        }
       }
-    }));
+      if validation_mode == ValidationMode::Strict && !failures.is_empty() {
+        return Err(Error::FailedInstructions(failures));
+      }
+      let disk_space = disk_space_requirement(&software_location, &downloads, disk_space_budget)?;
+      let updates = downloads.into_iter().map(|download_entry| PlannedUpdate {
+        path: download_entry.target_path.strip_prefix(&software_location).map(Path::to_path_buf).unwrap_or(download_entry.target_path),
+        delta: download_entry.mirror_path.starts_with("delta/"),
+        download_size: download_entry.download_size,
+      }).collect();
+      Ok(Plan { updates, deletions, disk_space })
+    }).await?
+  }
+
+  /// Lists every file that was moved into quarantine by a factory reset, oldest first
+  pub fn list_quarantined(&self) -> Result<Vec<QuarantinedFile>, Error> {
+    list_quarantined(&self.software_location)
+  }
+
+  /// Moves a quarantined file back to where it was, fails if a file already exists at that location
+  pub fn restore_quarantined(&self, file: &QuarantinedFile) -> Result<(), Error> {
+    restore_quarantined(&self.software_location, file)
+  }
+
+  /// Removes quarantine folders that are older than `max_age`
+  pub fn purge_quarantine(&self, max_age: Duration) -> Result<(), Error> {
+    purge_quarantine(&self.software_location, max_age)
+  }
+
+  /// The directory holding the retained patch files in mirror layout, None if patch files are not retained
+  pub fn retained_location(&self) -> Option<PathBuf> {
+    match &self.patch_file_retention {
+      PatchFileRetention::Remove => None,
+      PatchFileRetention::Retain => Some(self.software_location.join("patcher").join("mirror")),
+      PatchFileRetention::RetainIn(location) => Some(location.clone()),
+    }
+  }
+
+  /// Copies the retained patch files and instructions to `destination` in mirror layout, so it can be served as a mirror
+  ///
+  /// Returns the amount of files exported.
+  pub fn export_retained(&self, destination: impl AsRef<Path>) -> Result<u64, Error> {
+    let location = self.retained_location().ok_or_else(|| Error::InvalidInput(format!("Patch files are not retained")))?;
+    export_retained(&location, destination.as_ref())
+  }
+
+  /// Called with the report of every run that succeeded
+  pub fn add_success_callback(&self, func: impl Fn(&PatchReport) + Send + Sync + 'static) -> Result<(), Error> {
+    self.subscribers.lock()?.success.push(Box::new(func));
+    Ok(())
+  }
+
+  /// Called with the error of every run that failed
+  pub fn add_failure_callback(&self, func: impl Fn(&Error) + Send + Sync + 'static) -> Result<(), Error> {
+    self.subscribers.lock()?.failure.push(Box::new(func));
+    Ok(())
+  }
+
+  /// Called with the progress of every run, about 4 times per second
+  pub fn add_progress_callback(&self, func: impl Fn(&Progress) + Send + Sync + 'static) -> Result<(), Error> {
+    self.subscribers.lock()?.progress.push(Box::new(func));
+    Ok(())
+  }
+
+  /// Marks a run as started and returns the progress callback to pass along to it, the run stays marked until the guard is dropped
+  fn begin_run(&mut self) -> Result<(Box<dyn Fn(&Progress) + Send>, RunGuard), Error> {
+    if self.in_progress.swap(true, Ordering::SeqCst) {
+      return Err(Error::AlreadyStarted());
+    }
+    let run_guard = RunGuard { in_progress: self.in_progress.clone() };
+    if self.context.cancelled.load(Ordering::Relaxed) {
+      self.context = Arc::new(FutureContext::new());
+    }
+    let subscribers = self.subscribers.clone();
+    Ok((Box::new(move |progress| {
+      if let Ok(subscribers) = subscribers.lock() {
+        for progress_callback in &subscribers.progress {
+          call_subscriber("progress", || progress_callback(progress));
+        }
+      }
+    }), run_guard))
   }
 
   pub async fn get_handle(mut self) -> Option<tokio::task::JoinHandle<()>> {
     self.join_handle.take()
   } 
 
-  pub async fn cancel(mut self) -> Result<(), ()> {
+  /// Stops the running patch and waits for it to stop, the patcher can be started again afterwards
+  pub async fn cancel(&mut self) -> Result<(), ()> {
     self.context.stop()?;
     if let Some(join_handle) = self.join_handle.take() {
       let _ = join_handle.await;
@@ -93,4 +263,72 @@ impl Patcher {
   pub fn resume(&self) -> Result<(), ()> {
     self.context.resume()
   }
-}
\ No newline at end of file
+}
+
+/// Reports the outcome of a run to the event writer, the callbacks and the handle of the run
+///
+/// The run is marked as finished before the handle resolves, so it can be started again right away.
+fn complete(progress: &Progress, result: Result<(), Error>, subscribers: &Mutex<Subscribers>, run_guard: RunGuard, sender: oneshot::Sender<Result<PatchReport, Error>>) {
+  let outcome = result.and_then(|_| progress.finish_report());
+  match &outcome {
+    Ok(report) => progress.emit(Event::Finished { progress: progress.snapshot(), report: report.clone() }),
+    Err(e) => progress.emit(Event::Failed { error: e.to_string() }),
+  }
+  if let Ok(subscribers) = subscribers.lock() {
+    match &outcome {
+      Ok(report) => {
+        tracing::info!("Calling success callbacks");
+        subscribers.success.iter().for_each(|success_callback| call_subscriber("success", || success_callback(report)));
+      },
+      Err(e) => {
+        tracing::info!("Calling failure callbacks");
+        subscribers.failure.iter().for_each(|failure_callback| call_subscriber("failure", || failure_callback(e)));
+      }
+    }
+  }
+  drop(run_guard);
+  // Nobody is waiting for the outcome if the handle was dropped
+  let _ = sender.send(outcome);
+}
+
+/// Calls a subscriber, a panic in it is logged instead of poisoning the subscribers or ending the run
+fn call_subscriber(kind: &str, callback: impl FnOnce()) {
+  if std::panic::catch_unwind(std::panic::AssertUnwindSafe(callback)).is_err() {
+    tracing::error!("A {} callback panicked", kind);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{NamedUrl, PatcherBuilder};
+  use crate::structures::TempDir;
+
+  #[tokio::test]
+  async fn panicking_callbacks_do_not_block_the_next_run() {
+    let temp_dir = TempDir::new("patcher_panic");
+    let mut builder = PatcherBuilder::new();
+    builder.set_software_location(temp_dir.path());
+    builder.set_software_information(vec![NamedUrl { name: "local".to_string(), url: "http://127.0.0.1:1/".to_string() }], "1.0".to_string(), "0".repeat(64));
+    let mut patcher = builder.build().unwrap();
+    patcher.add_failure_callback(|_| panic!("failure callback")).unwrap();
+    for _ in 0..2 {
+      assert!(patcher.start_patching().await.unwrap().await.is_err());
+      assert!(!patcher.in_progress.load(Ordering::SeqCst));
+    }
+    patcher.add_success_callback(|_| {}).unwrap();
+  }
+
+  #[test]
+  fn plan_runs_on_the_runtime_handle_outside_of_a_runtime() {
+    let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build().unwrap();
+    let temp_dir = TempDir::new("patcher_plan");
+    let mut builder = PatcherBuilder::new();
+    builder.set_software_location(temp_dir.path());
+    builder.set_software_information(vec![NamedUrl { name: "local".to_string(), url: "http://127.0.0.1:1/".to_string() }], "1.0".to_string(), "0".repeat(64));
+    builder.set_runtime_handle(runtime.handle().clone());
+    let patcher = builder.build().unwrap();
+    // The instructions can not be downloaded, but nothing panics for a lack of a runtime
+    assert!(futures::executor::block_on(patcher.plan()).is_err());
+  }
+}
@@ -1,19 +1,34 @@
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::sync::atomic::AtomicBool;
 
 use crate::pausable::FutureContext;
-use crate::{NamedUrl, Progress};
+use crate::{DEFAULT_UNVERSIONED_ALLOWLIST, NamedUrl, Progress};
 use crate::patcher::Patcher;
-use crate::structures::{Error, Mirrors};
+use crate::structures::{ContentStore, DeduplicationMode, Error, EventWriter, Executor, Mirrors, FailureCallback, PatchFileRetention, PatchReport, ProgressCallback, Subscribers, SuccessCallback, UnversionedMode, ValidationMode};
 
 pub struct PatcherBuilder {
-  pub(crate) software_location: Option<String>,
+  pub(crate) software_location: Option<PathBuf>,
   pub(crate) mirrors: Option<Vec<NamedUrl>>,
+  /// Mirrors that were tested already, used instead of `mirrors` so they are not tested again
+  pub(crate) tested_mirrors: Option<Mirrors>,
   pub(crate) version: Option<String>,
   pub(crate) instructions_hash: Option<String>,
-  pub(crate) success_callback: Option<Box<dyn FnOnce() + Send>>,
-  pub(crate) failure_callback: Option<Box<dyn FnOnce(Error) + Send>>,
-  pub(crate) progress_callback: Option<Box<dyn Fn(&Progress) + Send>>,
+  pub(crate) validation_mode: ValidationMode,
+  pub(crate) unversioned_allowlist: Vec<String>,
+  pub(crate) unversioned_mode: UnversionedMode,
+  pub(crate) quarantine_max_age: Option<Duration>,
+  pub(crate) case_insensitive_paths: bool,
+  pub(crate) deduplication_mode: DeduplicationMode,
+  pub(crate) content_store: Option<ContentStore>,
+  pub(crate) patch_file_retention: PatchFileRetention,
+  pub(crate) disk_space_budget: Option<u64>,
+  pub(crate) event_writer: Option<EventWriter>,
+  pub(crate) success_callback: Option<SuccessCallback>,
+  pub(crate) failure_callback: Option<FailureCallback>,
+  pub(crate) progress_callback: Option<ProgressCallback>,
+  pub(crate) executor: Executor,
 }
 
 impl PatcherBuilder {
@@ -21,57 +36,264 @@ impl PatcherBuilder {
         Self {
             software_location: None,
             mirrors: None,
+            tested_mirrors: None,
             version: None,
             instructions_hash: None,
+            validation_mode: ValidationMode::default(),
+            unversioned_allowlist: DEFAULT_UNVERSIONED_ALLOWLIST.iter().map(|pattern| pattern.to_string()).collect(),
+            unversioned_mode: UnversionedMode::default(),
+            quarantine_max_age: None,
+            case_insensitive_paths: false,
+            deduplication_mode: DeduplicationMode::default(),
+            content_store: None,
+            patch_file_retention: PatchFileRetention::default(),
+            disk_space_budget: None,
+            event_writer: None,
             success_callback: None,
             failure_callback: None,
-            progress_callback: None
+            progress_callback: None,
+            executor: Executor::default(),
         }
     }
 
-    pub fn set_software_location(&mut self, software_location: String) -> &mut Self {
-        self.software_location = Some(software_location);
+    pub fn set_software_location(&mut self, software_location: impl AsRef<Path>) -> &mut Self {
+        self.software_location = Some(software_location.as_ref().to_path_buf());
         self
     }
 
     
     pub fn set_software_information(&mut self, mirrors: Vec<NamedUrl>, version: String, instructions_hash: String) -> &mut Self {
         self.mirrors = Some(mirrors);
+        self.tested_mirrors = None;
         self.version = Some(version);
         self.instructions_hash = Some(instructions_hash);
         self
     }
 
-    pub fn set_success_callback(&mut self, func: Box<dyn FnOnce() + Send>) -> &mut Self 
+    /// Like `set_software_information`, reusing mirrors that were tested for another patcher
+    pub(crate) fn set_tested_mirrors(&mut self, mirrors: Mirrors, version: String, instructions_hash: String) -> &mut Self {
+        self.mirrors = None;
+        self.tested_mirrors = Some(mirrors);
+        self.version = Some(version);
+        self.instructions_hash = Some(instructions_hash);
+        self
+    }
+
+    /// Sets whether failing instructions abort the patch (`Strict`, the default) or are skipped (`Lenient`)
+    pub fn set_validation_mode(&mut self, validation_mode: ValidationMode) -> &mut Self {
+        self.validation_mode = validation_mode;
+        self
+    }
+
+    /// Replaces the glob patterns, relative to the software location, of files that are never removed as unversioned
+    ///
+    /// Defaults to `DEFAULT_UNVERSIONED_ALLOWLIST`
+    pub fn set_unversioned_allowlist(&mut self, patterns: Vec<String>) -> &mut Self {
+        self.unversioned_allowlist = patterns;
+        self
+    }
+
+    /// Sets whether a factory reset deletes unversioned files (`Delete`, the default) or moves them into quarantine (`Quarantine`)
+    pub fn set_unversioned_mode(&mut self, unversioned_mode: UnversionedMode) -> &mut Self {
+        self.unversioned_mode = unversioned_mode;
+        self
+    }
+
+    /// Quarantine folders older than `max_age` are purged at the end of every factory reset, `None` keeps them forever
+    pub fn set_quarantine_max_age(&mut self, max_age: Option<Duration>) -> &mut Self {
+        self.quarantine_max_age = max_age;
+        self
+    }
+
+    /// Match manifest paths case-insensitively against files that already exist on disk
+    ///
+    /// Useful for installs on case-sensitive filesystems, such as Linux servers or Wine/Proton prefixes.
+    pub fn set_case_insensitive_paths(&mut self, case_insensitive_paths: bool) -> &mut Self {
+        self.case_insensitive_paths = case_insensitive_paths;
+        self
+    }
+
+    /// Sets how content that is shared by several files is placed after it has been patched once
+    pub fn set_deduplication_mode(&mut self, deduplication_mode: DeduplicationMode) -> &mut Self {
+        self.deduplication_mode = deduplication_mode;
+        self
+    }
+
+    /// Shares verified files and patch files with other installs through a content-addressed cache directory
+    ///
+    /// When `max_size` is set, the least recently used files are evicted at the end of every patch until the cache fits.
+    pub fn set_content_store(&mut self, location: impl AsRef<Path>, max_size: Option<u64>) -> &mut Self {
+        self.content_store = Some(ContentStore::new(location.as_ref().to_path_buf(), max_size));
+        self
+    }
+
+    /// Sets whether the downloaded patch files are removed after patching (`Remove`, the default) or retained in mirror layout
+    ///
+    /// A `RetainIn` directory inside of the software location is never removed as unversioned.
+    pub fn set_patch_file_retention(&mut self, patch_file_retention: PatchFileRetention) -> &mut Self {
+        self.patch_file_retention = patch_file_retention;
+        self
+    }
+
+    /// Enables the low-disk mode: patch files, together with the files they patch, take at most `budget` bytes at the same time
+    ///
+    /// Each patch file is removed right after it has been applied, making room for the next download, so it can not be combined with retaining patch files.
+    pub fn set_disk_space_budget(&mut self, budget: Option<u64>) -> &mut Self {
+        self.disk_space_budget = budget;
+        self
+    }
+
+    /// Writes the progress of every run to `writer` as newline-delimited JSON, for automation that can not use callbacks
+    ///
+    /// Every line is an object with a `timestamp` in milliseconds and an `event`: `state`, `progress`, `file_patched`,
+    /// `file_removed`, `file_quarantined`, `file_failed`, followed by either `finished` or `failed` as the last line.
+    pub fn set_event_writer(&mut self, writer: impl std::io::Write + Send + 'static) -> &mut Self {
+        self.event_writer = Some(EventWriter::new(Box::new(writer)));
+        self
+    }
+
+    /// Runs the patcher's tasks on the runtime of `handle`, e.g. a dedicated runtime separate from the UI
+    ///
+    /// Without it, tasks run on the runtime `start_patching` is called from. Only tokio runtimes are supported.
+    pub fn set_runtime_handle(&mut self, handle: tokio::runtime::Handle) -> &mut Self {
+        self.executor = Executor::new(handle);
+        self
+    }
+
+    /// Called with a report of what was done every time patching finished successfully, see `Patcher::add_success_callback` to add more
+    pub fn set_success_callback(&mut self, func: Box<dyn Fn(&PatchReport) + Send + Sync>) -> &mut Self 
     {
         self.success_callback = Some(func);
         self
     }
 
-    pub fn set_failure_callback(&mut self, func: Box<dyn FnOnce(Error) + Send>) -> &mut Self 
+    /// Called with the error every time patching failed
+    pub fn set_failure_callback(&mut self, func: Box<dyn Fn(&Error) + Send + Sync>) -> &mut Self 
     {
         self.failure_callback = Some(func);
         self
     }
 
-    pub fn set_progress_callback(&mut self, func: Box<dyn Fn(&Progress) + Send>) -> &mut Self 
+    pub fn set_progress_callback(&mut self, func: Box<dyn Fn(&Progress) + Send + Sync>) -> &mut Self 
     {
         self.progress_callback = Some(func);
         self
     }
 
+    /// Validates the provided settings and creates a `Patcher` from them
     pub fn build(self) -> Result<Patcher, Error> {
+        let software_location = self.software_location.ok_or(Error::MissingField("software_location"))?;
+        if self.mirrors.is_none() && self.tested_mirrors.is_none() {
+            return Err(Error::MissingField("mirrors"));
+        }
+        let version = self.version.ok_or(Error::MissingField("version"))?;
+        let instructions_hash = self.instructions_hash.ok_or(Error::MissingField("instructions_hash"))?;
+
+        if software_location.as_os_str().is_empty() {
+            return Err(Error::InvalidInput(format!("software_location is empty")));
+        }
+        if version.is_empty() {
+            return Err(Error::InvalidInput(format!("version is empty")));
+        }
+        if instructions_hash.len() != 64 || !instructions_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidInput(format!("instructions_hash is not a SHA256 hash: {}", instructions_hash)));
+        }
+        let mut unversioned_allowlist = self.unversioned_allowlist.iter().map(|pattern| glob::Pattern::new(pattern)).collect::<Result<Vec<_>, _>>()?;
+        // Patch files retained inside of the install are never unversioned
+        if let PatchFileRetention::RetainIn(location) = &self.patch_file_retention {
+            if let Ok(relative) = location.strip_prefix(&software_location) {
+                let pattern = relative.components().map(|component| glob::Pattern::escape(&component.as_os_str().to_string_lossy())).collect::<Vec<_>>().join("/");
+                if !pattern.is_empty() {
+                    unversioned_allowlist.push(glob::Pattern::new(&pattern)?);
+                }
+            }
+        }
+        let mirrors = match self.tested_mirrors {
+            Some(mirrors) => mirrors.with_version(&version),
+            None => Mirrors::new(self.mirrors.unwrap_or_default(), version.clone()),
+        };
+        if self.disk_space_budget == Some(0) {
+            return Err(Error::InvalidInput(format!("disk_space_budget is zero")));
+        }
+        if self.disk_space_budget.is_some() && self.patch_file_retention != PatchFileRetention::Remove {
+            return Err(Error::InvalidInput("disk_space_budget can not be combined with retaining patch files".to_string()));
+        }
+        if mirrors.is_empty() {
+            return Err(Error::NoMirrors());
+        }
 
         Ok(Patcher {
             in_progress: Arc::new(AtomicBool::new(false)),
             join_handle: None,
-            software_location: self.software_location.expect(""),
-            mirrors: Mirrors::new(self.mirrors.expect(""), self.version.expect("")),
-            instructions_hash: self.instructions_hash.expect(""),
-            success_callback: self.success_callback,
-            failure_callback: self.failure_callback,
-            progress_callback: self.progress_callback,
+            software_location,
+            mirrors,
+            version,
+            instructions_hash,
+            validation_mode: self.validation_mode,
+            unversioned_allowlist,
+            unversioned_mode: self.unversioned_mode,
+            quarantine_max_age: self.quarantine_max_age,
+            case_insensitive_paths: self.case_insensitive_paths,
+            deduplication_mode: self.deduplication_mode,
+            content_store: self.content_store,
+            patch_file_retention: self.patch_file_retention,
+            disk_space_budget: self.disk_space_budget,
+            event_writer: self.event_writer,
+            subscribers: Arc::new(Mutex::new(Subscribers {
+                success: self.success_callback.into_iter().collect(),
+                failure: self.failure_callback.into_iter().collect(),
+                progress: self.progress_callback.into_iter().collect(),
+            })),
+            executor: self.executor,
             context: Arc::new(FutureContext::new())
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::functions::find_unversioned;
+    use crate::structures::TempDir;
+
+    #[test]
+    fn build_without_software_location() {
+        let mut builder = PatcherBuilder::new();
+        builder.set_software_information(vec![NamedUrl { name: "Localhost".to_string(), url: "http://127.0.0.1/".to_string() }], "1".to_string(), "A".repeat(64));
+        assert!(matches!(builder.build(), Err(Error::MissingField("software_location"))));
+    }
+
+    #[test]
+    fn build_with_invalid_instructions_hash() {
+        let mut builder = PatcherBuilder::new();
+        builder.set_software_location("/tmp/renegadex");
+        builder.set_software_information(vec![NamedUrl { name: "Localhost".to_string(), url: "http://127.0.0.1/".to_string() }], "1".to_string(), "not a hash".to_string());
+        assert!(matches!(builder.build(), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn build_with_budget_and_retention() {
+        let mut builder = PatcherBuilder::new();
+        builder.set_software_location("/tmp/renegadex");
+        builder.set_software_information(vec![NamedUrl { name: "Localhost".to_string(), url: "http://127.0.0.1/".to_string() }], "1".to_string(), "A".repeat(64));
+        builder.set_disk_space_budget(Some(1024)).set_patch_file_retention(PatchFileRetention::Retain);
+        assert!(matches!(builder.build(), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn retained_patch_files_inside_the_install_are_not_unversioned() {
+        let temp_dir = TempDir::new("builder_retain_in");
+        let software_location = temp_dir.path();
+        let retained = software_location.join("Seeds").join("5.89").join("full").join("ABCD");
+        std::fs::create_dir_all(retained.parent().unwrap()).unwrap();
+        std::fs::write(&retained, b"patch").unwrap();
+        std::fs::write(software_location.join("unknown.txt"), b"").unwrap();
+
+        let mut builder = PatcherBuilder::new();
+        builder.set_software_location(software_location);
+        builder.set_software_information(vec![NamedUrl { name: "Localhost".to_string(), url: "http://127.0.0.1/".to_string() }], "5.89".to_string(), "A".repeat(64));
+        builder.set_patch_file_retention(PatchFileRetention::RetainIn(software_location.join("Seeds")));
+        let patcher = builder.build().unwrap();
+        assert_eq!(find_unversioned(software_location, &vec![], &patcher.unversioned_allowlist, false).unwrap(), vec![software_location.join("unknown.txt")]);
+    }
+}
@@ -1,8 +1,10 @@
+use std::path::PathBuf;
+
 use super::DownloadEntry;
 
 #[derive(Debug)]
 pub enum Action {
     Download(DownloadEntry),
-    Delete(String),
+    Delete(PathBuf),
     Nothing
-}
\ No newline at end of file
+}
@@ -0,0 +1,12 @@
+use std::path::PathBuf;
+
+/// A content-addressed cache directory that can be shared by several installs
+///
+/// Files are stored by their SHA256 hash: the patched files, the files that were already up to date and the downloaded patch files.
+#[derive(Debug, Clone)]
+pub struct ContentStore {
+  /// The directory holding the cached files
+  pub location: PathBuf,
+  /// The maximum combined size of the cached files in bytes, least recently used files are evicted first
+  pub max_size: Option<u64>,
+}
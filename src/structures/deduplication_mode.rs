@@ -0,0 +1,14 @@
+/// Determines how a file is placed at every other target that shares its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeduplicationMode {
+  /// The patched file is copied to the other targets
+  Copy,
+  /// The other targets are hard-linked to the patched file, falls back to copying where the filesystem does not support it
+  HardLink,
+}
+
+impl Default for DeduplicationMode {
+  fn default() -> Self {
+    Self::Copy
+  }
+}
@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Semaphore;
+
+/// Limits the disk space taken at the same time by the patch files being downloaded and applied, together with the files they patch
+///
+/// Sizes are tracked in KiB, so a budget of up to 4 TiB fits in the permits of a single acquire.
+#[derive(Debug, Clone)]
+pub(crate) struct DiskBudget {
+  pub(crate) semaphore: Arc<Semaphore>,
+  /// The budget in KiB
+  pub(crate) permits: u32,
+  /// The permits held for each patch file, returned once it is released
+  pub(crate) acquired: Arc<Mutex<HashMap<PathBuf, u32>>>,
+}
@@ -0,0 +1,10 @@
+/// The disk space an update needs on top of the current installation, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DiskSpace {
+  /// Space needed for the patch files that still have to be downloaded
+  pub downloads: u64,
+  /// Space needed while patching: the targets of new files plus the largest delta patch (source + target)
+  pub patching: u64,
+  /// Free space on the disk holding the software location
+  pub available: u64,
+}
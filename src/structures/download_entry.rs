@@ -1,15 +1,17 @@
+use std::path::PathBuf;
+
 #[derive(Clone, Debug)]
 pub struct DownloadEntry {
   /// The path relative to a mirror
   pub mirror_path: String,
   /// The path of the downloaded file
-  pub download_path: String,
+  pub download_path: PathBuf,
   /// The expected size of the downloaded file
   pub download_size: u64,
   /// The expected hash of the downloaded file
   pub download_hash: String,
   /// Path to target file
-  pub target_path: String,
+  pub target_path: PathBuf,
   /// The expected target hash after patching
   pub target_hash: String
-}
\ No newline at end of file
+}
@@ -12,7 +12,7 @@ pub enum Error {
 	IoError(std::io::Error),
 	NoMirrors(),
 	NotUtf8(std::string::FromUtf8Error),
-	JsonError(json::Error),
+	JsonError(serde_json::Error),
 
 	None(String),
 	InvalidServer(),
@@ -21,6 +21,30 @@ pub enum Error {
 	InvalidJson(String, String),
 	OutOfRetries(&'static str),
 	StripPrefix(std::path::StripPrefixError),
+	InvalidPattern(glob::PatternError),
+	/// A channel between the verification, download and patching loops was closed early
+	ChannelClosed(&'static str),
+
+	// Builder related errors:
+	/// A required field was not set on the `PatcherBuilder`
+	MissingField(&'static str),
+	/// A field on the `PatcherBuilder` holds an unusable value, the argument describes why
+	InvalidInput(String),
+	/// A run of the `Patcher` is already in progress, it can be started again once that run finished
+	AlreadyStarted(),
+	/// The `Patcher` is not in a state that allows the call, e.g. resuming a patcher that is not paused
+	InvalidState(&'static str),
+
+	/// One or more instructions could not be parsed or processed, lists every failed path with its error
+	FailedInstructions(Vec<(String, Error)>),
+	/// An entry of instructions.json failed validation, first argument is the path of the entry, second argument is the reason
+	InvalidManifest(String, String),
+	/// instructions.json has a newer `ManifestVersion` than this patcher supports
+	UnsupportedManifestVersion(u32),
+	/// A manifest path could end up outside of the software location, first argument is the path, second argument is the reason
+	UnsafePath(String, &'static str),
+	/// The disk holding the software location does not have enough free space for the update, both values are in bytes
+	InsufficientDiskSpace { required: u64, available: u64 },
 
 
 	// Download related errors:
@@ -0,0 +1,42 @@
+use serde::Serialize;
+
+use crate::structures::PatchReport;
+
+/// An event written as a line of JSON by the event writer of `PatcherBuilder::set_event_writer`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum Event {
+  /// The patcher moved on to another phase, `action` matches `Progress::get_current_action`
+  State { action: String },
+  /// A periodic snapshot of `Progress`, written at most once per second
+  Progress(ProgressSnapshot),
+  /// A file was patched, or copied from an identical file that was patched
+  FilePatched { path: String, hash: String },
+  /// A file was removed, either because it is no longer versioned or because it is unversioned
+  FileRemoved { path: String },
+  /// A file was moved into quarantine
+  FileQuarantined { path: String },
+  /// An instruction could not be processed, with `Lenient` validation the patch continues without it
+  FileFailed { path: String, error: String },
+  /// The patch finished successfully, always the last event
+  Finished {
+    #[serde(flatten)]
+    progress: ProgressSnapshot,
+    report: PatchReport,
+  },
+  /// The patch failed, always the last event
+  Failed { error: String },
+}
+
+/// The counters of `Progress` at one moment
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct ProgressSnapshot {
+  pub processed_instructions: u64,
+  pub total_instructions: u64,
+  pub downloaded_files: u64,
+  pub total_download_files: u64,
+  pub downloaded_bytes: u64,
+  pub total_download_bytes: u64,
+  pub patched_files: u64,
+  pub total_patch_files: u64,
+}
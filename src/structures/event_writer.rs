@@ -0,0 +1,8 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Writes `Event`s as newline-delimited JSON to a writer supplied by the user
+#[derive(Clone)]
+pub(crate) struct EventWriter {
+  pub(crate) writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
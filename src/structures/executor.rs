@@ -0,0 +1,10 @@
+use tokio::runtime::Handle;
+
+/// Spawns the tasks of a `Patcher`, on the runtime passed to `PatcherBuilder::set_runtime_handle` or else the runtime it is started on
+///
+/// Every task of a run is spawned through the executor of its `Patcher`, which is passed down explicitly.
+/// This only wraps a tokio `Handle`, other async runtimes are not supported.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Executor {
+  pub(crate) handle: Option<Handle>,
+}
@@ -0,0 +1,9 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// The SHA256 hashes of the files of an install, so planning and patching in the same run only hash each file once
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileHashes {
+  pub(crate) hashes: Arc<Mutex<HashMap<PathBuf, String>>>,
+}
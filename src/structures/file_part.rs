@@ -1,13 +1,15 @@
+use std::path::PathBuf;
+
 #[derive(Clone, Debug)]
 pub struct FilePart {
-    pub file: String,
+    pub file: PathBuf,
     pub part_byte: u64,
     pub from: u64,
     pub to: u64,
 }
 
 impl FilePart {
-    pub fn new(file: String, part_byte: u64, from: u64, to: u64) -> Self {
+    pub fn new(file: PathBuf, part_byte: u64, from: u64, to: u64) -> Self {
         Self {
             file,
             part_byte,
@@ -15,4 +17,4 @@ impl FilePart {
             to,
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// An installation tracked by an `InstallManager`, such as the game, its beta, a dedicated server or the SDK
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Install {
+  /// The unique name of the install
+  pub name: String,
+  /// The release channel the install follows, e.g. `release` or `beta`
+  pub channel: String,
+  /// The directory of the install
+  pub software_location: PathBuf,
+  /// The version the install was last patched to, None if it was never patched by the manager
+  pub version: Option<String>,
+}
+
+/// The version a release channel is currently on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelRelease {
+  pub version: String,
+  /// The SHA256 hash of the instructions.json of the version
+  pub instructions_hash: String,
+}
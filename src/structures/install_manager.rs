@@ -0,0 +1,21 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::structures::{ChannelRelease, ContentStore, Install, Mirrors};
+use crate::PatcherBuilder;
+
+pub(crate) type ConfigureInstall = Box<dyn Fn(&Install, &mut PatcherBuilder) + Send + Sync>;
+
+/// Tracks several installs, each following its own channel, and patches them using shared mirrors and a shared content store
+///
+/// The installs and the version each of them is on are persisted in a JSON state file.
+pub struct InstallManager {
+  pub(crate) state_path: PathBuf,
+  pub(crate) installs: Vec<Install>,
+  /// Shared by every install, so they are only tested before the first patch
+  pub(crate) mirrors: Mirrors,
+  pub(crate) releases: HashMap<String, ChannelRelease>,
+  pub(crate) content_store: Option<ContentStore>,
+  /// Called on the builder of every install before it is patched
+  pub(crate) configure: Option<ConfigureInstall>,
+}
@@ -0,0 +1,18 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// What an install was last patched to, written to `INSTALL_STATE_FILE` in the software location after every successful patch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallState {
+  pub version: String,
+  /// The SHA256 hash of the instructions.json the install was patched with
+  pub instructions_hash: String,
+  /// When the patch finished, in seconds since the unix epoch
+  pub timestamp: u64,
+  /// The version of this library that patched the install
+  pub patcher_version: String,
+  /// Every versioned file relative to the software location, with its SHA256 hash
+  pub files: BTreeMap<PathBuf, String>,
+}
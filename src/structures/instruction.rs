@@ -1,8 +1,10 @@
+use std::path::PathBuf;
+
 /// An instruction
 #[derive(Debug, Clone)]
 pub(crate) struct Instruction {
-  /// Path to which the instruction applies
-  pub path: String,
+  /// Path relative to the software location to which the instruction applies
+  pub path: PathBuf,
   /// SHA256 hash of this file during the previous patch, None if this is a new file
   pub previous_hash: Option<String>,
   /// SHA256 hash of this file during current patch, None if the file is to be deleted/moved
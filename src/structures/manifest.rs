@@ -0,0 +1,43 @@
+use serde::Deserialize;
+
+/// The newest `ManifestVersion` this version of the patcher understands
+pub const SUPPORTED_MANIFEST_VERSION: u32 = 2;
+
+/// The contents of instructions.json
+///
+/// Version 1 manifests are a bare array of entries, newer manifests wrap the entries in an object
+/// carrying a `ManifestVersion`, unknown fields are ignored so new fields can be added without
+/// breaking older launchers.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Manifest {
+  Versioned {
+    #[serde(rename = "ManifestVersion")]
+    manifest_version: u32,
+    #[serde(rename = "Instructions")]
+    instructions: Vec<serde_json::Value>,
+  },
+  Legacy(Vec<serde_json::Value>),
+}
+
+/// A single entry of instructions.json
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ManifestEntry {
+  /// Windows style path relative to the software location
+  pub path: String,
+  /// SHA256 hash of this file during the previous patch, None if this is a new file
+  pub old_hash: Option<String>,
+  /// SHA256 hash of this file during current patch, None if the file is to be deleted
+  pub new_hash: Option<String>,
+  /// SHA256 hash of Full vcdiff patch file
+  pub compressed_hash: Option<String>,
+  /// SHA256 hash of Delta vcdiff patch file
+  pub delta_hash: Option<String>,
+  /// Size of `Full` vcdiff patch file
+  pub full_replace_size: u64,
+  /// Size of `Delta` vcdiff patch file
+  pub delta_size: u64,
+  /// Does file have a Delta vcdiff patch file
+  pub has_delta: bool,
+}
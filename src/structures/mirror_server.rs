@@ -0,0 +1,16 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// A minimal HTTP server that serves retained patch files in mirror layout, so other installs on the LAN can patch from it
+///
+/// Use `url()` as the mirror URL of the other installs' `PatcherBuilder`.
+#[derive(Debug)]
+pub struct MirrorServer {
+  pub(crate) root: PathBuf,
+  pub(crate) local_addr: SocketAddr,
+  pub(crate) shutdown: Option<oneshot::Sender<()>>,
+  pub(crate) join_handle: Option<JoinHandle<()>>,
+}
@@ -3,4 +3,6 @@ use crate::structures::Mirror;
 #[derive(Debug, Clone)]
 pub struct Mirrors {
   pub mirrors: Vec<Mirror>,
+  /// Whether the speed of the mirrors was tested already, they are not tested again before downloading then
+  pub tested: bool,
 }
\ No newline at end of file
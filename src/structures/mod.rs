@@ -9,6 +9,9 @@ pub(crate) use mirrors::Mirrors as Mirrors;
 
 mod instruction_group;
 
+mod manifest;
+pub(crate) use manifest::{Manifest, ManifestEntry, SUPPORTED_MANIFEST_VERSION};
+
 mod instruction;
 pub(crate) use instruction::Instruction as Instruction;
 
@@ -18,8 +21,8 @@ pub(crate) use response::Response as Response;
 mod error;
 pub use error::Error as Error;
 
-mod directory;
-pub(crate) use directory::Directory as Directory;
+mod path_index;
+pub(crate) use path_index::PathIndex as PathIndex;
 
 mod buffered_writer;
 pub(crate) use buffered_writer::BufWriter as BufWriter;
@@ -34,4 +37,73 @@ mod named_url;
 pub use named_url::NamedUrl as NamedUrl;
 
 mod file_part;
-pub use file_part::FilePart as FilePart;
\ No newline at end of file
+pub use file_part::FilePart as FilePart;
+
+mod validation_mode;
+pub use validation_mode::ValidationMode as ValidationMode;
+
+mod unversioned_mode;
+pub use unversioned_mode::UnversionedMode as UnversionedMode;
+
+mod quarantined_file;
+pub use quarantined_file::QuarantinedFile as QuarantinedFile;
+
+mod deduplication_mode;
+pub use deduplication_mode::DeduplicationMode as DeduplicationMode;
+
+mod content_store;
+pub use content_store::ContentStore as ContentStore;
+
+mod patch_file_retention;
+pub use patch_file_retention::PatchFileRetention as PatchFileRetention;
+#[cfg(feature = "mirror-server")]
+mod mirror_server;
+#[cfg(feature = "mirror-server")]
+pub use mirror_server::MirrorServer as MirrorServer;
+
+mod disk_space;
+pub use disk_space::DiskSpace as DiskSpace;
+
+mod disk_budget;
+pub(crate) use disk_budget::DiskBudget as DiskBudget;
+
+mod file_hashes;
+pub(crate) use file_hashes::FileHashes as FileHashes;
+
+mod plan;
+pub use plan::{Plan, PlannedUpdate};
+
+mod event;
+pub(crate) use event::{Event, ProgressSnapshot};
+
+mod event_writer;
+pub(crate) use event_writer::EventWriter as EventWriter;
+
+mod patch_report;
+pub use patch_report::PatchReport as PatchReport;
+
+mod patch_handle;
+pub use patch_handle::PatchHandle as PatchHandle;
+
+mod subscribers;
+pub(crate) use subscribers::{FailureCallback, ProgressCallback, SuccessCallback, Subscribers};
+
+mod executor;
+pub(crate) use executor::Executor as Executor;
+
+mod run_guard;
+pub(crate) use run_guard::RunGuard as RunGuard;
+
+mod install;
+pub use install::{ChannelRelease, Install};
+
+mod install_manager;
+pub use install_manager::InstallManager as InstallManager;
+
+mod install_state;
+pub use install_state::InstallState as InstallState;
+
+#[cfg(test)]
+mod temp_dir;
+#[cfg(test)]
+pub(crate) use temp_dir::TempDir as TempDir;
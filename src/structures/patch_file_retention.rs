@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+/// Determines what happens to the downloaded patch files once patching is done
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchFileRetention {
+  /// The patch files are removed together with the patcher folder
+  Remove,
+  /// The patch files are kept in the patcher folder of the install, in mirror layout
+  Retain,
+  /// The patch files are moved into the given directory, in mirror layout
+  RetainIn(PathBuf),
+}
+
+impl Default for PatchFileRetention {
+  fn default() -> Self {
+    Self::Remove
+  }
+}
@@ -0,0 +1,11 @@
+use tokio::sync::oneshot;
+
+use crate::structures::{Error, PatchReport};
+
+/// Resolves to the outcome of a run started by `Patcher::start_patching` or `Patcher::factory_reset`
+///
+/// Dropping the handle does not stop the run, use `Patcher::cancel` for that.
+#[derive(Debug)]
+pub struct PatchHandle {
+  pub(crate) receiver: oneshot::Receiver<Result<PatchReport, Error>>,
+}
@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// A summary of a finished patch, passed to the success callback
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PatchReport {
+  /// Files that were compared against the instructions
+  pub verified_files: u64,
+  /// Delta patch files that were downloaded
+  pub delta_downloads: u64,
+  /// Full patch files that were downloaded
+  pub full_downloads: u64,
+  /// Files that were patched, including copies of duplicate content
+  pub patched_files: u64,
+  /// Files that were removed, either because they are no longer part of the software or because they were unversioned
+  pub deleted_files: u64,
+  /// Files that were restored from their backup, either as the newest version or as the source of a delta download
+  pub restored_files: u64,
+  /// Bytes downloaded from each mirror, keyed by the mirror URL
+  pub mirror_bytes: BTreeMap<String, u64>,
+  /// Time spent on each phase, in order, named after the current action of `Progress` during that phase
+  pub phases: Vec<(String, Duration)>,
+  /// Files that were skipped by lenient validation or whose download failed, the install is incomplete unless this is 0
+  pub skipped_files: u64,
+  /// Problems that did not stop patching, such as files skipped by lenient validation
+  pub warnings: Vec<String>,
+}
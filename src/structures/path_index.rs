@@ -0,0 +1,11 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// A hashed index of relative file paths and every directory containing them
+#[derive(Debug)]
+pub struct PathIndex {
+  /// Whether lookups ignore the case of the path, as Windows does
+  pub case_insensitive: bool,
+  pub directories: HashSet<PathBuf>,
+  pub files: HashSet<PathBuf>,
+}
@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use crate::structures::DiskSpace;
+
+/// What patching would change in the software location, see `Patcher::plan`
+#[derive(Debug, Clone)]
+pub struct Plan {
+  /// Files that are missing or outdated and will be patched
+  pub updates: Vec<PlannedUpdate>,
+  /// Files that are no longer part of the software and will be removed
+  pub deletions: Vec<PathBuf>,
+  pub disk_space: DiskSpace,
+}
+
+/// A file that will be patched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedUpdate {
+  /// The path of the file relative to the software location
+  pub path: PathBuf,
+  /// Whether the existing file is patched with a delta, or replaced using a full patch file
+  pub delta: bool,
+  /// The size of the patch file that has to be downloaded
+  pub download_size: u64,
+}
@@ -1,5 +1,8 @@
 use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::structures::{EventWriter, PatchReport};
 
 #[derive(Clone)]
 pub struct Progress {
@@ -9,4 +12,9 @@ pub struct Progress {
   pub downloaded_bytes: Arc<(AtomicU64, AtomicU64)>,
   pub patched_files: Arc<(AtomicU64, AtomicU64, AtomicU64)>,
   pub patched_bytes: Arc<(AtomicU64, AtomicU64)>,
+  pub(crate) events: Option<EventWriter>,
+  /// The report handed to the success callback, filled in while patching
+  pub(crate) report: Arc<Mutex<PatchReport>>,
+  /// When the current action started, to time the phases in the report
+  pub(crate) action_started: Arc<Mutex<Instant>>,
 }
\ No newline at end of file
@@ -0,0 +1,13 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// A file that was moved into quarantine by a factory reset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedFile {
+  /// The moment the file was quarantined
+  pub quarantined_at: SystemTime,
+  /// The path of the file relative to the software location, this is where it gets restored to
+  pub path: PathBuf,
+  /// The current location of the file inside of the quarantine
+  pub location: PathBuf,
+}
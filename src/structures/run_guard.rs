@@ -0,0 +1,7 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+/// Keeps a `Patcher` marked as running until it is dropped, which also happens when the run panics
+pub(crate) struct RunGuard {
+  pub(crate) in_progress: Arc<AtomicBool>,
+}
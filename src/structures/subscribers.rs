@@ -0,0 +1,13 @@
+use crate::structures::{Error, PatchReport, Progress};
+
+pub(crate) type SuccessCallback = Box<dyn Fn(&PatchReport) + Send + Sync>;
+pub(crate) type FailureCallback = Box<dyn Fn(&Error) + Send + Sync>;
+pub(crate) type ProgressCallback = Box<dyn Fn(&Progress) + Send + Sync>;
+
+/// The callbacks of a `Patcher`, called on every run
+#[derive(Default)]
+pub(crate) struct Subscribers {
+  pub(crate) success: Vec<SuccessCallback>,
+  pub(crate) failure: Vec<FailureCallback>,
+  pub(crate) progress: Vec<ProgressCallback>,
+}
@@ -0,0 +1,6 @@
+use std::path::PathBuf;
+
+/// A directory inside the system temp directory for tests, removed with its contents when dropped
+pub(crate) struct TempDir {
+  pub(crate) path: PathBuf,
+}
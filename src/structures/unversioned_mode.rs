@@ -0,0 +1,14 @@
+/// Determines what happens to unversioned files during a factory reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnversionedMode {
+  /// Unversioned files and directories are removed for good
+  Delete,
+  /// Unversioned files and directories are moved into a timestamped folder inside `QUARANTINE_DIRECTORY`, from where they can be restored
+  Quarantine,
+}
+
+impl Default for UnversionedMode {
+  fn default() -> Self {
+    Self::Delete
+  }
+}
@@ -0,0 +1,14 @@
+/// Determines how the patcher deals with instructions or files that fail to process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationMode {
+  /// Every failure is collected and the patch fails with `Error::FailedInstructions` listing them
+  Strict,
+  /// Failures are logged and skipped, the patch can succeed with an incomplete install
+  Lenient,
+}
+
+impl Default for ValidationMode {
+  fn default() -> Self {
+    Self::Strict
+  }
+}
@@ -1,2 +0,0 @@
-mod as_string;
-pub use as_string::AsString as AsString;
\ No newline at end of file